@@ -0,0 +1,188 @@
+use std::collections::{BinaryHeap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use schematic2bls::largest_cube::extraction::{get_largest_cubes, LargestCubeEntry};
+use schematic2bls::largest_cube::mapping::{
+    grid_to_largest_cubes, idx_1d_from, idx_3d_from, GridReader, GridSizes,
+};
+use schematic2bls::largest_cube::tiling::{default_thread_count, get_largest_cubes_tiled};
+use schematic2bls::largest_cube::{CubeSizes, LargestCube};
+use schematic2bls::*;
+
+const RUNS: u32 = 10;
+
+/// Returns the fastest of several runs of the function, along with
+/// whatever the last run produced.
+fn fastest_of<T>(runs: u32, mut function: impl FnMut() -> T) -> (Duration, T) {
+    let mut fastest = Duration::MAX;
+    let mut output = None;
+
+    for _ in 0..runs {
+        let started_at = Instant::now();
+        output = Some(function());
+        fastest = fastest.min(started_at.elapsed());
+    }
+
+    (
+        fastest,
+        output.expect("schematic2bls: Benchmark ran zero times."),
+    )
+}
+
+/// Returns the fastest of several runs of the function, where each
+/// run is handed a fresh input from setup that is not timed.
+fn fastest_of_batched<I, T>(
+    runs: u32,
+    mut setup: impl FnMut() -> I,
+    mut function: impl FnMut(I) -> T,
+) -> (Duration, T) {
+    let mut fastest = Duration::MAX;
+    let mut output = None;
+
+    for _ in 0..runs {
+        let input = setup();
+        let started_at = Instant::now();
+        output = Some(function(input));
+        fastest = fastest.min(started_at.elapsed());
+    }
+
+    (
+        fastest,
+        output.expect("schematic2bls: Benchmark ran zero times."),
+    )
+}
+
+/// Returns whether claiming the Largest Cube overlapped a spot that
+/// was already claimed, in which case nothing is claimed.
+///
+/// Spots are marked as they are checked, and unmarked again on an
+/// overlap, as get_largest_cubes did before claimed spots were kept
+/// in a bitmap.
+fn mark_visited_in_hash_set(
+    largest_cube: &LargestCube,
+    sizes: &GridSizes,
+    visited: &mut HashSet<usize>,
+    scale: usize,
+) -> bool {
+    let side_length = largest_cube.side_length as usize / scale;
+
+    let x = largest_cube.indexes.0 / scale;
+    let y = largest_cube.indexes.1 / scale;
+    let z = largest_cube.indexes.2 / scale;
+
+    let mut found_indexes = Vec::new();
+    for i in (x + 1 - side_length..=x).rev() {
+        for j in (y + 1 - side_length..=y).rev() {
+            for k in (z + 1 - side_length..=z).rev() {
+                let idx_1d = idx_1d_from(i, j, k, sizes);
+                if !visited.insert(idx_1d) {
+                    for visited_idx in found_indexes {
+                        visited.remove(&visited_idx);
+                    }
+                    return true;
+                }
+
+                found_indexes.push(idx_1d);
+            }
+        }
+    }
+
+    false
+}
+
+/// Returns the same Largest Cubes as get_largest_cubes, keeping track
+/// of claimed spots in a HashSet so that it can be compared against.
+fn get_largest_cubes_hash_set(largest_cube_grid: GridReader, scale: u16) -> Vec<LargestCube> {
+    let cube_sizes = CubeSizes::default();
+    let sizes = largest_cube_grid.size_cloned();
+    let mut max_heap = largest_cube_grid
+        .data()
+        .iter()
+        .enumerate()
+        .filter(|(_, grid_item)| **grid_item != 0)
+        .map(|(idx, grid_item)| LargestCubeEntry {
+            data: *grid_item,
+            idx,
+        })
+        .collect::<BinaryHeap<LargestCubeEntry>>();
+    let mut visited = HashSet::new();
+
+    let mut largest_cubes = Vec::new();
+    while let Some(largest_cube_entry) = max_heap.pop() {
+        let idx_1d = largest_cube_entry.idx;
+        if visited.contains(&idx_1d) {
+            continue;
+        }
+
+        let mut idx_3d = idx_3d_from(idx_1d, &sizes);
+        idx_3d.0 *= scale as usize;
+        idx_3d.1 *= scale as usize;
+        idx_3d.2 *= scale as usize;
+
+        let mut largest_cube = LargestCube {
+            side_length: cube_sizes.largest_fitting_or_scale(largest_cube_entry.data, scale),
+            indexes: idx_3d,
+        };
+        while mark_visited_in_hash_set(&largest_cube, &sizes, &mut visited, scale as usize) {
+            largest_cube.side_length =
+                cube_sizes.largest_fitting_or_scale(largest_cube.side_length - scale, scale);
+        }
+
+        largest_cubes.push(largest_cube);
+    }
+
+    largest_cubes
+}
+
+fn bench_schematic(schematic_path: &str, scale: u16) {
+    let model = load_schematic(Path::new(schematic_path));
+    let voxel_grid = parse_grid_from_model(model);
+
+    let (dp_time, largest_cubes_grid) =
+        fastest_of(RUNS, || grid_to_largest_cubes(&voxel_grid, scale));
+    let (extraction_time, largest_cubes) = fastest_of_batched(
+        RUNS,
+        || largest_cubes_grid.clone(),
+        |largest_cubes_grid| get_largest_cubes(largest_cubes_grid, scale),
+    );
+    let (hash_set_time, hash_set_cubes) = fastest_of_batched(
+        RUNS,
+        || largest_cubes_grid.clone(),
+        |largest_cubes_grid| get_largest_cubes_hash_set(largest_cubes_grid, scale),
+    );
+    assert_eq!(
+        largest_cubes, hash_set_cubes,
+        "schematic2bls: HashSet extraction found different cubes."
+    );
+    let threads = default_thread_count();
+    let (tiled_time, _) = fastest_of(RUNS, || {
+        get_largest_cubes_tiled(&voxel_grid, scale, &CubeSizes::default(), threads)
+    });
+
+    println!(
+        "{}: {} filled voxels, {} cubes",
+        schematic_path,
+        voxel_grid.filled_count(),
+        largest_cubes.len()
+    );
+    println!("  grid_to_largest_cubes: {:.3}s", dp_time.as_secs_f64());
+    println!(
+        "  get_largest_cubes:     {:.3}s",
+        extraction_time.as_secs_f64()
+    );
+    println!(
+        "  with a HashSet:        {:.3}s",
+        hash_set_time.as_secs_f64()
+    );
+    println!(
+        "  tiled on {} threads:   {:.3}s",
+        threads,
+        tiled_time.as_secs_f64()
+    );
+}
+
+fn main() {
+    bench_schematic("assets/peachs_castle.schematic", 4);
+    bench_schematic("assets/peachs_castle_4.schematic", 4);
+}
@@ -0,0 +1,60 @@
+use schematic2bls::blockland::catalog::BrickCatalog;
+use schematic2bls::*;
+
+use std::env;
+use std::path::Path;
+
+const USAGE: &str = "Usage: bls2schematic <path_to_save_file> [scaling_factor] [--schem]";
+
+fn main() {
+    let (flags, execution_args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    if execution_args.len() < 2 || execution_args.len() > 3 {
+        eprintln!("{}", USAGE);
+        return;
+    }
+
+    let mut schematic_extension = ".schematic";
+    for flag in &flags {
+        match flag.as_str() {
+            "--schem" => schematic_extension = ".schem",
+            _ => {
+                eprintln!("bls2schematic: Unknown option {}", flag);
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
+    }
+
+    let save_file_arg = execution_args
+        .get(1)
+        .expect("bls2schematic: path_to_save_file not provided.");
+    let save_file_path = Path::new(save_file_arg);
+    let default_scaling_factor = 4;
+    let scaling_factor = if let Some(scaling_arg) = execution_args.get(2) {
+        scaling_arg.parse::<u8>().unwrap_or(default_scaling_factor)
+    } else {
+        default_scaling_factor
+    };
+
+    let mut schematic_file_name = save_file_path
+        .file_stem()
+        .expect("bls2schematic: Could not get file stem from save file path.")
+        .to_os_string();
+    schematic_file_name.push(schematic_extension);
+    let schematic_file_name = schematic_file_name.into_string().expect(
+        "bls2schematic: Could not convert OS String into normal String for Schematic File Name.",
+    );
+
+    let save_file = load_save_file(save_file_path);
+    let (voxel_grid, unknown_count) =
+        parse_grid_from_save_file(&save_file, &BrickCatalog::new(), scaling_factor as u16);
+    if unknown_count > 0 {
+        eprintln!(
+            "bls2schematic: {} bricks were left out, since their size is unknown.",
+            unknown_count
+        );
+    }
+
+    write_schematic(&voxel_grid, schematic_file_name);
+}
@@ -1,15 +1,191 @@
+use schematic2bls::blockland::events::{interactive_block_rules, EventRule};
+use schematic2bls::blockland::mapping::{Elevation, Origin};
+use schematic2bls::blockland::naming::NamingRule;
+use schematic2bls::blockland::ordering::BrickOrder;
+use schematic2bls::blockland::ownership::{BrickOwners, OwnedRegion};
+use schematic2bls::blockland::region::Region;
+use schematic2bls::blockland::save_file::{today, BlsWriter, BRICK_COUNT_PLACEHOLDER};
+use schematic2bls::blockland::splitting::SaveSplit;
+use schematic2bls::decomposition::{built_in_decomposer_named, built_in_decomposers};
+use schematic2bls::largest_cube::{tiling::default_thread_count, CubeSizes};
+use schematic2bls::save_writer::{built_in_save_writer_named, built_in_save_writers, SaveWriter};
 use schematic2bls::*;
 
 use std::env;
 use std::path::Path;
+use std::time::Instant;
+
+const USAGE: &str =
+    "Usage: schematic2bls <path_to_schematic> [scaling_factor] [--fill] [--ramps] [--cube-sizes=<sizes>] [--algorithm=<name>] [--threads=<count>] [--compare] [--stream] [--order=<order>] [--origin=<origin>] [--keep-elevation] [--merge-into=<save_file>] [--offset=<x,y,z>] [--allow-overlap] [--split-bricks=<count>] [--split-tiles=<studs>] [--format=<format>] [--owner=<bl_id>] [--no-owner] [--owner-region=<x1,y1,z1,x2,y2,z2,bl_id>] [--description=<line>] [--describe] [--interactive-blocks] [--event=<rule>] [--name=<rule>]";
 
 fn main() {
-    let execution_args: Vec<String> = env::args().collect();
+    let (flags, execution_args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
     if execution_args.len() < 2 || execution_args.len() > 3 {
-        eprintln!("Usage: schematic2bls <path_to_schematic> [scaling_factor]");
+        eprintln!("{}", USAGE);
         return;
     }
 
+    let mut fill_cavities = false;
+    let mut place_ramps = false;
+    let mut cube_sizes = CubeSizes::default();
+    let mut algorithm = "maximal-cubes";
+    let mut threads = default_thread_count();
+    let mut compare_algorithms = false;
+    let mut stream_model = false;
+    let mut brick_order = None;
+    let mut origin = Origin::default();
+    let mut elevation = Elevation::PlaceOnGround;
+    let mut base_save_file_path = None;
+    let mut offset = (0.0, 0.0, 0.0);
+    let mut allow_overlap = false;
+    let mut save_split = None;
+    let mut save_writer = built_in_save_writers().remove(0);
+    let mut brick_owners = BrickOwners::default();
+    let mut sets_owners = false;
+    let mut description = Vec::new();
+    let mut describe_conversion = false;
+    let mut include_interactive_blocks = false;
+    let mut event_rules = Vec::new();
+    let mut naming_rules = Vec::new();
+    for flag in &flags {
+        let (flag_name, flag_value) = flag.split_once('=').unwrap_or((flag, ""));
+        match flag_name {
+            "--fill" => fill_cavities = true,
+            "--ramps" => place_ramps = true,
+            "--cube-sizes" => {
+                let side_lengths = flag_value
+                    .split(',')
+                    .map(|side_length| side_length.trim().parse::<u16>())
+                    .collect::<Result<Vec<u16>, _>>()
+                    .expect(
+                        "schematic2bls: --cube-sizes must be a comma separated list of numbers.",
+                    );
+                cube_sizes = CubeSizes::new(&side_lengths);
+            }
+            "--algorithm" => algorithm = flag_value,
+            "--threads" => {
+                threads = flag_value
+                    .parse::<usize>()
+                    .expect("schematic2bls: --threads must be a number.")
+            }
+            "--compare" => compare_algorithms = true,
+            "--stream" => stream_model = true,
+            "--origin" => {
+                origin = match flag_value {
+                    "first-brick" => Origin::FirstBrick,
+                    "corner" => Origin::Corner,
+                    "center" => Origin::Center,
+                    "source" => Origin::Source,
+                    _ => {
+                        eprintln!("schematic2bls: Unknown origin {}", flag_value);
+                        eprintln!("Origins: first-brick, corner, center, source");
+                        return;
+                    }
+                }
+            }
+            "--keep-elevation" => elevation = Elevation::KeepOriginal,
+            "--merge-into" => base_save_file_path = Some(Path::new(flag_value)),
+            "--offset" => {
+                let coordinates = flag_value
+                    .split(',')
+                    .map(|coordinate| coordinate.trim().parse::<f32>())
+                    .collect::<Result<Vec<f32>, _>>();
+                let Ok([x, y, z]) = coordinates.as_deref() else {
+                    eprintln!("schematic2bls: --offset must be three comma separated numbers.");
+                    return;
+                };
+                offset = (*x, *y, *z);
+            }
+            "--allow-overlap" => allow_overlap = true,
+            "--description" => description.push(flag_value.to_string()),
+            "--describe" => describe_conversion = true,
+            "--interactive-blocks" => include_interactive_blocks = true,
+            "--event" => {
+                let Some(event_rule) = EventRule::parse(flag_value) else {
+                    eprintln!("schematic2bls: Could not read event rule {}", flag_value);
+                    eprintln!("Event rules look like color=<id>:<input>,<delay>,<target>,<output>[,<parameters>], where the brick can also be matched by block=<id> or region=<x1,y1,z1,x2,y2,z2>.");
+                    return;
+                };
+                event_rules.push(event_rule);
+            }
+            "--name" => {
+                let Some(naming_rule) = NamingRule::parse(flag_value) else {
+                    eprintln!("schematic2bls: Could not read naming rule {}", flag_value);
+                    eprintln!("Naming rules look like block=<id>:<name> or region=<x1,y1,z1,x2,y2,z2>:<name>, where the name is made up of letters, digits and underscores.");
+                    return;
+                };
+                naming_rules.push(naming_rule);
+            }
+            "--owner" => {
+                brick_owners.default_owner = Some(
+                    flag_value
+                        .parse::<u32>()
+                        .expect("schematic2bls: --owner must be a BL_ID."),
+                );
+                sets_owners = true;
+            }
+            "--no-owner" => {
+                brick_owners.default_owner = None;
+                sets_owners = true;
+            }
+            "--owner-region" => {
+                let owned_region = flag_value.rsplit_once(',').and_then(|(corners, owner_id)| {
+                    Some((Region::parse(corners)?, owner_id.trim().parse().ok()?))
+                });
+                let Some((region, owner_id)) = owned_region else {
+                    eprintln!("schematic2bls: --owner-region must be six comma separated numbers followed by a BL_ID.");
+                    return;
+                };
+                brick_owners.regions.push(OwnedRegion { region, owner_id });
+                sets_owners = true;
+            }
+            "--format" => {
+                let Some(named_save_writer) = built_in_save_writer_named(flag_value) else {
+                    let format_names = built_in_save_writers()
+                        .iter()
+                        .map(|save_writer| save_writer.name().to_string())
+                        .collect::<Vec<String>>();
+                    eprintln!("schematic2bls: Unknown format {}", flag_value);
+                    eprintln!("Formats: {}", format_names.join(", "));
+                    return;
+                };
+                save_writer = named_save_writer;
+            }
+            "--split-bricks" => {
+                save_split = Some(SaveSplit::BrickCount(
+                    flag_value
+                        .parse::<usize>()
+                        .expect("schematic2bls: --split-bricks must be a number."),
+                ))
+            }
+            "--split-tiles" => {
+                save_split = Some(SaveSplit::Tiles(
+                    flag_value
+                        .parse::<usize>()
+                        .expect("schematic2bls: --split-tiles must be a number."),
+                ))
+            }
+            "--order" => {
+                let Some(named_order) = BrickOrder::named(flag_value) else {
+                    let order_names = BrickOrder::ALL
+                        .iter()
+                        .map(|brick_order| brick_order.name())
+                        .collect::<Vec<&str>>();
+                    eprintln!("schematic2bls: Unknown order {}", flag_value);
+                    eprintln!("Orders: {}", order_names.join(", "));
+                    return;
+                };
+                brick_order = Some(named_order);
+            }
+            _ => {
+                eprintln!("schematic2bls: Unknown option {}", flag);
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
+    }
+
     let model_arg = execution_args
         .get(1)
         .expect("schematic2bls: path_to_schematic not provided.");
@@ -21,20 +197,246 @@ fn main() {
         default_scaling_factor
     };
 
-    let model = load_schematic(model_path);
-    let voxel_grid = parse_grid_from_model(model);
-    let largest_cubes = extract_largest_cubes_from(voxel_grid, scaling_factor as u16);
-    let bricks = extract_bricks_from(largest_cubes);
+    if !save_writer.writes_cuboids()
+        && !cube_sizes.side_lengths().contains(&(scaling_factor as u16))
+    {
+        eprintln!(
+            "schematic2bls: Blocks are placed as {}x Cubes where no bigger cube fits, but {} is not one of the cube sizes. Add it with --cube-sizes, or pick a scaling factor that is.",
+            scaling_factor, scaling_factor
+        );
+        return;
+    }
+
+    let Some(decomposer) = built_in_decomposer_named(algorithm, &cube_sizes, threads) else {
+        let algorithm_names = built_in_decomposers(&cube_sizes, threads)
+            .iter()
+            .map(|decomposer| decomposer.name().to_string())
+            .collect::<Vec<String>>();
+        eprintln!("schematic2bls: Unknown algorithm {}", algorithm);
+        eprintln!("Algorithms: {}", algorithm_names.join(", "));
+        return;
+    };
 
     let mut save_file_name = model_path
         .file_stem()
         .expect("schematic2bls: Could not get file stem from model path.")
         .to_os_string();
-    save_file_name.push(".bls");
+    save_file_name.push(format!(".{}", save_writer.extension()));
 
     let save_file_name = save_file_name.into_string().expect(
         "schematic2bls: Could not convert OS String into normal String for Save File Name.",
     );
 
-    write_save_file(&bricks, save_file_name);
+    let writes_bls = save_writer.name() == "bls";
+    if !writes_bls
+        && (stream_model
+            || base_save_file_path.is_some()
+            || sets_owners
+            || !description.is_empty()
+            || describe_conversion
+            || include_interactive_blocks
+            || !event_rules.is_empty()
+            || !naming_rules.is_empty())
+    {
+        eprintln!(
+            "schematic2bls: Only --format=bls can be combined with --stream, --merge-into, owners, descriptions, events or names."
+        );
+        return;
+    }
+    if save_writer.writes_cuboids() && (brick_order.is_some() || save_split.is_some()) {
+        eprintln!(
+            "schematic2bls: --format={} writes each box found as one brick, so it cannot be combined with --order or splitting.",
+            save_writer.name()
+        );
+        return;
+    }
+
+    let unmatchable = event_rules
+        .iter()
+        .map(|event_rule| &event_rule.matcher)
+        .chain(naming_rules.iter().map(|naming_rule| &naming_rule.matcher))
+        .any(|matcher| !matcher.can_match_converted(include_interactive_blocks));
+    if unmatchable {
+        eprintln!(
+            "schematic2bls: block=<id> only matches doors, trapdoors, buttons and levers, and only with --interactive-blocks."
+        );
+        return;
+    }
+
+    if describe_conversion {
+        let model_file_name = model_path
+            .file_name()
+            .map_or(model_arg.clone(), |file_name| {
+                file_name.to_string_lossy().to_string()
+            });
+        description.push(format!(
+            "Converted from {} by schematic2bls",
+            model_file_name
+        ));
+        description.push(format!("Scale: {}", scaling_factor));
+        description.push(format!("Bricks: {}", BRICK_COUNT_PLACEHOLDER));
+        description.push(format!("Date: {}", today()));
+    }
+    if include_interactive_blocks {
+        event_rules.splice(0..0, interactive_block_rules());
+    }
+    let bls_writer = BlsWriter {
+        brick_owners,
+        description,
+        event_rules,
+        naming_rules,
+    };
+    // The BLS writer found by name has none of the owners,
+    // descriptions, events or names asked for.
+    let save_writer: &dyn SaveWriter = if writes_bls {
+        &bls_writer
+    } else {
+        save_writer.as_ref()
+    };
+
+    if stream_model {
+        let reorders_bricks = brick_order.is_some_and(|order| order != BrickOrder::Extraction);
+        if fill_cavities
+            || place_ramps
+            || include_interactive_blocks
+            || compare_algorithms
+            || reorders_bricks
+            || base_save_file_path.is_some()
+            || save_split.is_some()
+            || algorithm != "maximal-cubes"
+        {
+            eprintln!(
+                "schematic2bls: --stream only works with the maximal-cubes algorithm and the extraction order, and without --fill, --ramps, --interactive-blocks, --compare, --merge-into or splitting."
+            );
+            return;
+        }
+
+        let streamed = stream_save_file_from(
+            model_path,
+            save_file_name,
+            scaling_factor as u16,
+            &cube_sizes,
+            threads,
+            origin,
+            elevation,
+            &bls_writer,
+        );
+        if let Err(error) = streamed {
+            eprintln!(
+                "schematic2bls: Could not stream schematic into save file: {}",
+                error
+            );
+        }
+        return;
+    }
+
+    let model = load_schematic(model_path);
+    let shaped_blocks = parse_shaped_blocks_from_model(&model, scaling_factor as u16);
+    let mut voxel_grid = parse_grid_from_model(model);
+    if fill_cavities {
+        voxel_grid = fill_cavities_in(voxel_grid);
+    }
+
+    let mut slopes = Vec::new();
+    if place_ramps {
+        slopes = extract_slopes_from(&voxel_grid, scaling_factor as u16);
+        slopes.retain(|slope| !shaped_blocks.occupies(slope));
+    }
+    slopes.extend(shaped_blocks.slopes);
+
+    if compare_algorithms {
+        for decomposer in built_in_decomposers(&cube_sizes, threads) {
+            let started_at = Instant::now();
+            let cuboids =
+                extract_cuboids_with(&voxel_grid, scaling_factor as u16, decomposer.as_ref());
+            let elapsed = started_at.elapsed();
+
+            let cuboid_count = cuboids.len();
+            let largest_cubes =
+                cuboids_to_largest_cubes(cuboids, &cube_sizes, scaling_factor as u16);
+            println!(
+                "{}: {} cuboids, {} bricks in {:.3}s",
+                decomposer.name(),
+                cuboid_count,
+                largest_cubes.len(),
+                elapsed.as_secs_f64()
+            );
+        }
+        return;
+    }
+
+    let cuboids = extract_cuboids_with(&voxel_grid, scaling_factor as u16, decomposer.as_ref());
+    if save_writer.writes_cuboids() {
+        let cuboids = cuboids_with_shapes(cuboids, shaped_blocks.cubes, &slopes);
+        write_cuboid_save_file_with(&cuboids, save_writer, origin, elevation, save_file_name);
+        return;
+    }
+    let mut largest_cubes = cuboids_to_largest_cubes(cuboids, &cube_sizes, scaling_factor as u16);
+    largest_cubes.extend(shaped_blocks.cubes);
+    let interactive_blocks = if include_interactive_blocks {
+        shaped_blocks.interactive_blocks
+    } else {
+        Vec::new()
+    };
+    let bricks = extract_bricks_with_blocks_placed_from(
+        largest_cubes,
+        slopes,
+        interactive_blocks,
+        origin,
+        elevation,
+    );
+    let bricks = match brick_order.unwrap_or_default() {
+        BrickOrder::Build => {
+            let (bricks, floating_count) = order_bricks_for_building(bricks);
+            if floating_count > 0 {
+                eprintln!(
+                    "schematic2bls: {} bricks have nothing beneath them leading down to the ground, and may fail to plant. They are at the end of the save file.",
+                    floating_count
+                );
+            }
+            bricks
+        }
+        brick_order => order_bricks_by(bricks, brick_order),
+    };
+
+    if let Some(save_split) = save_split {
+        if base_save_file_path.is_some() {
+            eprintln!("schematic2bls: Split save files cannot be merged into another save file.");
+            return;
+        }
+
+        let part_file_names =
+            write_split_save_files(bricks, save_split, save_writer, &save_file_name);
+        println!(
+            "schematic2bls: Wrote {} save files: {}",
+            part_file_names.len(),
+            part_file_names.join(", ")
+        );
+        return;
+    }
+
+    let Some(base_save_file_path) = base_save_file_path else {
+        write_save_file_with(&bricks, save_writer, save_file_name);
+        return;
+    };
+
+    let base_save_file = load_save_file(base_save_file_path);
+    let (mut merged_save_file, overlapping_count) =
+        merge_bricks_into(&base_save_file, &bricks, offset, &bls_writer);
+    if overlapping_count > 0 {
+        eprintln!(
+            "schematic2bls: {} bricks overlap bricks already in {}.",
+            overlapping_count,
+            base_save_file_path.display()
+        );
+        if !allow_overlap {
+            eprintln!("schematic2bls: Nothing was written. Move the model with --offset, or pass --allow-overlap to merge anyway.");
+            return;
+        }
+    }
+
+    if !bls_writer.description.is_empty() {
+        merged_save_file.description = bls_writer.description_of(merged_save_file.bricks.len());
+    }
+    write_merged_save_file(&merged_save_file, save_file_name);
 }
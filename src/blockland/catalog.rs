@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use super::parsing::SavedBrick;
+use super::BrickShape;
+
+/// How wide one stud is, in Blockland units.
+pub const STUD_WIDTH: f32 = 0.5;
+/// How tall one plate is, in Blockland units, where a regular brick
+/// is three plates tall.
+pub const PLATE_HEIGHT: f32 = 0.2;
+
+/// The corners of a brick with the smallest and largest X, Y and Z,
+/// in Blockland units.
+pub type CatalogBounds = ((f32, f32, f32), (f32, f32, f32));
+
+/// Looks up how much space a brick takes up by its UI name.
+///
+/// Bricks named the way Blockland names its default bricks, such as
+/// `2x4`, `1x1F`, `1x1x5` and `16x16 Base`, along with the cubes and
+/// ramps this crate places, are known without being added.
+#[derive(Default)]
+pub struct BrickCatalog {
+    dimensions: HashMap<String, (f32, f32, f32)>,
+}
+
+fn parse_studs(studs: &str) -> Option<f32> {
+    let studs = studs.parse::<u16>().ok()?;
+    (studs > 0).then_some(studs as f32)
+}
+
+/// Returns the dimensions of a brick following Blockland's default
+/// naming, or None if the name does not follow it.
+fn default_dimensions(ui_name: &str) -> Option<(f32, f32, f32)> {
+    if let Some((size, shape_name)) = ui_name.split_once("x ") {
+        let size = size.parse::<u16>().ok()?;
+        let shape = match shape_name {
+            "Cube" => BrickShape::Cube,
+            "Ramp" => BrickShape::Ramp,
+            "Long Ramp" => BrickShape::LongRamp,
+            _ => return None,
+        };
+        let (x_len, y_len, z_len) = shape.dimensions(size);
+
+        return Some((
+            x_len as f32 * STUD_WIDTH,
+            y_len as f32 * STUD_WIDTH,
+            z_len as f32 * STUD_WIDTH,
+        ));
+    }
+
+    // Plates are named like 1x2F, and baseplates like 16x16 Base.
+    let (studs, plates) = match ui_name
+        .strip_suffix(['F', 'f'])
+        .or_else(|| ui_name.strip_suffix(" Base"))
+    {
+        Some(studs) => (studs, 1.0),
+        None => (ui_name, 3.0),
+    };
+    let studs = studs.split('x').collect::<Vec<&str>>();
+    match studs[..] {
+        [width, length] => Some((
+            parse_studs(width)? * STUD_WIDTH,
+            parse_studs(length)? * STUD_WIDTH,
+            plates * PLATE_HEIGHT,
+        )),
+        [width, length, height] if plates == 3.0 => Some((
+            parse_studs(width)? * STUD_WIDTH,
+            parse_studs(length)? * STUD_WIDTH,
+            parse_studs(height)? * plates * PLATE_HEIGHT,
+        )),
+        _ => None,
+    }
+}
+
+impl BrickCatalog {
+    pub fn new() -> Self {
+        BrickCatalog::default()
+    }
+
+    /// Adds a brick that is x_len by y_len by z_len Blockland units
+    /// at angle 0, replacing whatever its name would otherwise mean.
+    pub fn with_brick(&mut self, ui_name: &str, dimensions: (f32, f32, f32)) {
+        self.dimensions.insert(ui_name.to_string(), dimensions);
+    }
+
+    /// Returns the length along X, width along Y and height along Z
+    /// of the brick at angle 0, in Blockland units, or None if the
+    /// brick is unknown.
+    pub fn dimensions_of(&self, ui_name: &str) -> Option<(f32, f32, f32)> {
+        self.dimensions
+            .get(ui_name)
+            .copied()
+            .or_else(|| default_dimensions(ui_name))
+    }
+
+    /// Returns where the brick reaches to, or None if the brick is
+    /// unknown.
+    pub fn bounds_of(&self, saved_brick: &SavedBrick) -> Option<CatalogBounds> {
+        let dimensions = self.dimensions_of(&saved_brick.ui_name)?;
+        let (x_len, y_len, z_len) = match saved_brick.angle_id % 2 {
+            0 => dimensions,
+            _ => (dimensions.1, dimensions.0, dimensions.2),
+        };
+        let (x, y, z) = saved_brick.position;
+
+        Some((
+            (x - x_len / 2.0, y - y_len / 2.0, z - z_len / 2.0),
+            (x + x_len / 2.0, y + y_len / 2.0, z + z_len / 2.0),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn know_default_bricks() {
+        let brick_catalog = BrickCatalog::new();
+
+        assert_eq!(
+            brick_catalog.dimensions_of("4x Cube"),
+            Some((2.0, 2.0, 2.0))
+        );
+        assert_eq!(
+            brick_catalog.dimensions_of("2x Long Ramp"),
+            Some((2.0, 1.0, 1.0))
+        );
+        assert_eq!(brick_catalog.dimensions_of("2x4"), Some((1.0, 2.0, 0.6)));
+        assert_eq!(brick_catalog.dimensions_of("1x2F"), Some((0.5, 1.0, 0.2)));
+        assert_eq!(brick_catalog.dimensions_of("1x1x5"), Some((0.5, 0.5, 3.0)));
+        assert_eq!(
+            brick_catalog.dimensions_of("32x32 Base"),
+            Some((16.0, 16.0, 0.2))
+        );
+        assert_eq!(brick_catalog.dimensions_of("Pine Tree"), None);
+    }
+
+    #[test]
+    fn added_bricks_come_first() {
+        let mut brick_catalog = BrickCatalog::new();
+        brick_catalog.with_brick("1x Cube Bottom", (0.5, 0.5, 0.4));
+        brick_catalog.with_brick("2x4", (1.0, 2.0, 1.0));
+
+        assert_eq!(
+            brick_catalog.dimensions_of("1x Cube Bottom"),
+            Some((0.5, 0.5, 0.4))
+        );
+        assert_eq!(brick_catalog.dimensions_of("2x4"), Some((1.0, 2.0, 1.0)));
+    }
+}
@@ -0,0 +1,236 @@
+use super::parsing::BrickEvent;
+use super::region::Region;
+use super::Brick;
+use crate::model::conversion::{BUTTON_IDS, DOOR_IDS};
+
+/// Which bricks an event rule gives its events to.
+#[derive(PartialEq, Debug, Clone)]
+pub enum BrickMatcher {
+    /// Bricks painted with this color of the colorset.
+    Color(usize),
+    /// Bricks converted from this Minecraft block ID.
+    SourceBlock(u8),
+    /// Bricks whose center lies within the region.
+    Region(Region),
+}
+
+impl BrickMatcher {
+    /// Returns the matcher written as `color=<color_id>`,
+    /// `block=<block_id>` or `region=<x1,y1,z1,x2,y2,z2>`.
+    pub fn parse(matcher: &str) -> Option<BrickMatcher> {
+        let (kind, value) = matcher.split_once('=')?;
+        match kind {
+            "color" => Some(BrickMatcher::Color(value.trim().parse().ok()?)),
+            "block" => Some(BrickMatcher::SourceBlock(value.trim().parse().ok()?)),
+            "region" => Some(BrickMatcher::Region(Region::parse(value)?)),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the matcher can find any brick of a converted
+    /// model, since only the bricks of doors, trapdoors, buttons and
+    /// levers remember which block they were converted from, and only
+    /// when interactive blocks are converted.
+    pub fn can_match_converted(&self, with_interactive_blocks: bool) -> bool {
+        match self {
+            BrickMatcher::SourceBlock(block_id) => {
+                with_interactive_blocks
+                    && (DOOR_IDS.contains(block_id) || BUTTON_IDS.contains(block_id))
+            }
+            _ => true,
+        }
+    }
+
+    pub fn matches(&self, brick: &Brick) -> bool {
+        match self {
+            BrickMatcher::Color(color_id) => brick.color_id == *color_id,
+            BrickMatcher::SourceBlock(block_id) => brick.source_block == Some(*block_id),
+            BrickMatcher::Region(region) => region.contains(brick.position),
+        }
+    }
+}
+
+/// Stands in for the name of the brick an event is on in its named
+/// target, since the name is not known until the brick is written.
+pub const OWN_NAME_PLACEHOLDER: &str = "{own_name}";
+/// The target Blockland writes for events aimed at named bricks.
+const NAMED_BRICK_TARGET: &str = "-1";
+
+/// Events to set up on every brick a matcher finds.
+#[derive(PartialEq, Debug, Clone)]
+pub struct EventRule {
+    pub matcher: BrickMatcher,
+    pub events: Vec<BrickEvent>,
+}
+
+/// Returns an enabled event, numbered once it is known which other
+/// events share its brick.
+fn enabled_event<'a>(
+    input_event: &str,
+    delay: u32,
+    target: &str,
+    output_event: &str,
+    parameters: impl IntoIterator<Item = &'a str>,
+) -> BrickEvent {
+    BrickEvent {
+        index: 0,
+        enabled: true,
+        input_event: input_event.to_string(),
+        delay,
+        target: target.to_string(),
+        named_target: String::new(),
+        output_event: output_event.to_string(),
+        parameters: parameters.into_iter().map(str::to_string).collect(),
+    }
+}
+
+impl EventRule {
+    /// Returns the rule written as a matcher and an event separated
+    /// by a colon, where the event is its input event, delay, target,
+    /// output event and parameters separated by commas, such as
+    /// `block=77:onActivate,0,Self,setColorFX,3`.
+    pub fn parse(rule: &str) -> Option<EventRule> {
+        let (matcher, event) = rule.split_once(':')?;
+        let mut fields = event.split(',').map(str::trim);
+        let input_event = fields.next().filter(|field| !field.is_empty())?;
+        let delay = fields.next()?.parse().ok()?;
+        let target = fields.next().filter(|field| !field.is_empty())?;
+        let output_event = fields.next().filter(|field| !field.is_empty())?;
+
+        Some(EventRule {
+            matcher: BrickMatcher::parse(matcher)?,
+            events: vec![enabled_event(
+                input_event,
+                delay,
+                target,
+                output_event,
+                fields,
+            )],
+        })
+    }
+}
+
+/// Returns rules making the bricks of doors and buttons work once
+/// clicked on.
+///
+/// Doors and trapdoors disappear for 5 seconds to let players through,
+/// where every brick sharing the name of the one clicked on
+/// disappears, so that both halves of a door open together. Buttons
+/// and levers glow for half a second.
+pub fn interactive_block_rules() -> Vec<EventRule> {
+    let mut open_door = enabled_event("onActivate", 0, NAMED_BRICK_TARGET, "disappear", ["5"]);
+    open_door.named_target = OWN_NAME_PLACEHOLDER.to_string();
+    let door_events = vec![open_door];
+    let button_events = vec![
+        enabled_event("onActivate", 0, "Self", "setColorFX", ["3"]),
+        enabled_event("onActivate", 500, "Self", "setColorFX", ["0"]),
+    ];
+
+    let door_rules = DOOR_IDS.iter().map(|block_id| EventRule {
+        matcher: BrickMatcher::SourceBlock(*block_id),
+        events: door_events.clone(),
+    });
+    let button_rules = BUTTON_IDS.iter().map(|block_id| EventRule {
+        matcher: BrickMatcher::SourceBlock(*block_id),
+        events: button_events.clone(),
+    });
+
+    door_rules.chain(button_rules).collect()
+}
+
+/// Returns the events of every rule matching the brick, in the order
+/// of the rules, numbered the way Blockland numbers a brick's events.
+pub fn events_for(event_rules: &[EventRule], brick: &Brick) -> Vec<BrickEvent> {
+    event_rules
+        .iter()
+        .filter(|event_rule| event_rule.matcher.matches(brick))
+        .flat_map(|event_rule| event_rule.events.iter().cloned())
+        .enumerate()
+        .map(|(index, mut brick_event)| {
+            brick_event.index = index;
+            brick_event
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::DEFAULT_COLOR_ID;
+
+    fn door_brick() -> Brick {
+        let mut brick = Brick::new((4, 4, 4), 4);
+        brick.source_block = Some(64);
+        brick
+    }
+
+    #[test]
+    fn parse_event_rule() {
+        let actual = EventRule::parse("color=5:onActivate,100,Self,setColor,3").unwrap();
+
+        assert_eq!(actual.matcher, BrickMatcher::Color(5));
+        assert_eq!(
+            actual.events,
+            vec![BrickEvent {
+                index: 0,
+                enabled: true,
+                input_event: "onActivate".to_string(),
+                delay: 100,
+                target: "Self".to_string(),
+                named_target: String::new(),
+                output_event: "setColor".to_string(),
+                parameters: vec!["3".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_region_matcher() {
+        let actual = EventRule::parse("region=0,0,0,8,8,8:onActivate,0,Self,disappear,5").unwrap();
+
+        assert!(actual.matcher.matches(&door_brick()));
+        assert_eq!(actual.events[0].parameters, vec!["5".to_string()]);
+    }
+
+    #[test]
+    fn block_matchers_only_match_interactive_blocks() {
+        let door_matcher = BrickMatcher::parse("block=64").unwrap();
+        let stone_matcher = BrickMatcher::parse("block=1").unwrap();
+
+        assert!(door_matcher.can_match_converted(true));
+        assert!(!door_matcher.can_match_converted(false));
+        assert!(!stone_matcher.can_match_converted(true));
+        assert!(BrickMatcher::Color(5).can_match_converted(false));
+    }
+
+    #[test]
+    fn reject_incomplete_event_rule() {
+        assert!(EventRule::parse("color=5").is_none());
+        assert!(EventRule::parse("color=5:onActivate,0,Self").is_none());
+        assert!(EventRule::parse("shape=5:onActivate,0,Self,setColor,3").is_none());
+        assert!(EventRule::parse("color=5:onActivate,soon,Self,setColor,3").is_none());
+    }
+
+    #[test]
+    fn number_events_of_every_matching_rule() {
+        let mut event_rules = interactive_block_rules();
+        let default_color_rule = format!("color={}:onActivate,0,Self,setColor,3", DEFAULT_COLOR_ID);
+        event_rules.push(EventRule::parse(&default_color_rule).unwrap());
+        event_rules.push(EventRule::parse("color=5:onActivate,0,Self,setColor,4").unwrap());
+
+        let actual = events_for(&event_rules, &door_brick());
+
+        let indexes_and_outputs: Vec<(usize, &str)> = actual
+            .iter()
+            .map(|brick_event| (brick_event.index, brick_event.output_event.as_str()))
+            .collect();
+        assert_eq!(indexes_and_outputs, vec![(0, "disappear"), (1, "setColor")]);
+    }
+
+    #[test]
+    fn plain_bricks_get_no_events() {
+        let actual = events_for(&interactive_block_rules(), &Brick::new((4, 4, 4), 4));
+
+        assert!(actual.is_empty());
+    }
+}
@@ -1,33 +1,165 @@
-use super::Brick;
+use super::{Brick, BrickShape, DEFAULT_COLOR_ID};
+use crate::slopes::Facing;
+
+/// Where a model ends up vertically once it is turned into bricks.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Elevation {
+    /// Moves the model down until its lowest brick rests on the
+    /// ground.
+    #[default]
+    PlaceOnGround,
+    /// Keeps the model as high above the ground as it was in the
+    /// voxel grid, so only bricks at the very bottom of the grid
+    /// touch the ground.
+    KeepOriginal,
+}
+
+/// Which point of the model ends up at X and Y of 0 once it is
+/// turned into bricks.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Origin {
+    /// The middle of the brick nearest the corner of the model's
+    /// bounding box, going by the corner of each brick with the
+    /// largest X, then Y, then Z.
+    #[default]
+    FirstBrick,
+    /// The corner of the model's bounding box with the smallest X
+    /// and Y.
+    Corner,
+    /// The middle of the model's bounding box, rounded down to a
+    /// whole stud.
+    Center,
+    /// Wherever 0 was in the voxel grid, so models converted from the
+    /// same source line up with each other.
+    Source,
+}
+
+/// The bounding box of a set of bricks, measured in the same units
+/// as the voxel grid before any bricks have been offset.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct ModelBounds {
+    start: (usize, usize, usize),
+    end: (usize, usize, usize),
+    /// The corners of the box whose corner with the largest X, Y and
+    /// Z comes first.
+    first_box: ((usize, usize, usize), (usize, usize, usize)),
+}
+
+impl ModelBounds {
+    /// Returns the bounds of the bricks, or None if there are none.
+    pub(crate) fn of<'a>(bricks: impl IntoIterator<Item = &'a Brick>) -> Option<ModelBounds> {
+        ModelBounds::of_boxes(bricks.into_iter().map(|brick| (brick.start(), brick.end())))
+    }
+
+    /// Returns the bounds of boxes given by their corners with the
+    /// smallest and largest X, Y and Z, or None if there are none.
+    pub(crate) fn of_boxes(
+        boxes: impl IntoIterator<Item = ((usize, usize, usize), (usize, usize, usize))>,
+    ) -> Option<ModelBounds> {
+        let mut model_bounds: Option<ModelBounds> = None;
+        for (start, end) in boxes {
+            match model_bounds.as_mut() {
+                Some(model_bounds) => model_bounds.include_box(start, end),
+                None => {
+                    model_bounds = Some(ModelBounds {
+                        start,
+                        end,
+                        first_box: (start, end),
+                    })
+                }
+            }
+        }
+
+        model_bounds
+    }
+
+    pub(crate) fn include(&mut self, brick: &Brick) {
+        self.include_box(brick.start(), brick.end());
+    }
+
+    fn include_box(&mut self, start: (usize, usize, usize), end: (usize, usize, usize)) {
+        self.start = (
+            self.start.0.min(start.0),
+            self.start.1.min(start.1),
+            self.start.2.min(start.2),
+        );
+        self.end = (
+            self.end.0.max(end.0),
+            self.end.1.max(end.1),
+            self.end.2.max(end.2),
+        );
+        if end < self.first_box.1 {
+            self.first_box = (start, end);
+        }
+    }
+
+    /// Returns the point of the voxel grid that ends up at 0 along
+    /// each axis once bricks are placed, which can fall halfway
+    /// between two units.
+    pub(crate) fn anchor(&self, origin: Origin, elevation: Elevation) -> (f32, f32, f32) {
+        let (x, y) = match origin {
+            Origin::FirstBrick => {
+                let (start, end) = self.first_box;
+                (
+                    (start.0 + end.0) as f32 / 2.0,
+                    (start.1 + end.1) as f32 / 2.0,
+                )
+            }
+            Origin::Corner => (self.start.0 as f32, self.start.1 as f32),
+            Origin::Center => (
+                ((self.start.0 + self.end.0) / 2) as f32,
+                ((self.start.1 + self.end.1) / 2) as f32,
+            ),
+            Origin::Source => (0.0, 0.0),
+        };
+        let z = match elevation {
+            Elevation::PlaceOnGround => self.start.2,
+            Elevation::KeepOriginal => 0,
+        };
+
+        (x, y, z as f32)
+    }
+}
 
 pub struct BrickBuilder {
     pub bricks: Vec<Brick>,
+    origin: Origin,
+    elevation: Elevation,
 }
 
 impl BrickBuilder {
     pub fn new() -> Self {
-        BrickBuilder { bricks: Vec::new() }
+        BrickBuilder {
+            bricks: Vec::new(),
+            origin: Origin::default(),
+            elevation: Elevation::default(),
+        }
     }
 
     pub fn with_brick(&mut self, brick: Brick) {
         self.bricks.push(brick);
     }
 
-    pub fn build(&mut self) -> Vec<Brick> {
-        let mut adjusted_coordinate_bricks = Vec::new();
+    pub fn with_origin(&mut self, origin: Origin) {
+        self.origin = origin;
+    }
 
-        let min_size = self
-            .bricks
-            .iter()
-            .min_by(|brick1, brick2| brick1.position.partial_cmp(&brick2.position).unwrap())
-            .unwrap()
-            .size;
+    pub fn with_elevation(&mut self, elevation: Elevation) {
+        self.elevation = elevation;
+    }
 
-        for brick in &self.bricks {
-            adjusted_coordinate_bricks.push(brick.calculate_right_offset(min_size));
-        }
+    /// Returns the bricks placed relative to the model's bounding box,
+    /// or nothing if there were no bricks to place.
+    pub fn build(&mut self) -> Vec<Brick> {
+        let Some(model_bounds) = ModelBounds::of(&self.bricks) else {
+            return Vec::new();
+        };
+        let anchor = model_bounds.anchor(self.origin, self.elevation);
 
-        adjusted_coordinate_bricks
+        self.bricks
+            .iter()
+            .map(|brick| brick.calculate_right_offset(anchor))
+            .collect()
     }
 }
 
@@ -43,6 +175,16 @@ fn right_to_center_coord(right_coord: f32, size: u16) -> f32 {
 
 impl Brick {
     pub fn new(right_xyz_coord: (usize, usize, usize), size: u16) -> Brick {
+        Brick::new_oriented(right_xyz_coord, size, BrickShape::Cube, Facing::PositiveX)
+    }
+
+    /// Returns a brick whose rotated footprint ends at right_xyz_coord.
+    pub fn new_oriented(
+        right_xyz_coord: (usize, usize, usize),
+        size: u16,
+        shape: BrickShape,
+        orientation: Facing,
+    ) -> Brick {
         Brick {
             position: (
                 right_xyz_coord.0 as f32,
@@ -50,26 +192,53 @@ impl Brick {
                 right_xyz_coord.2 as f32,
             ),
             size,
+            shape,
+            orientation,
+            color_id: DEFAULT_COLOR_ID,
+            source_block: None,
+            name: None,
             floored: true,
         }
     }
 
-    fn calculate_right_offset(&self, min_size: u16) -> Brick {
-        let x = right_to_center_coord(self.position.0, self.size);
-        let y = right_to_center_coord(self.position.1, self.size);
-        let floored = self.position.2 as usize == self.size as usize;
+    /// Returns the corner of the brick with the smallest X, Y and Z,
+    /// before it has been offset.
+    fn start(&self) -> (usize, usize, usize) {
+        let (x_len, y_len, z_len) = self.dimensions();
+        let (x, y, z) = self.end();
 
-        let min_size_offset = min_size as f32 / 4.0;
+        (x - x_len as usize, y - y_len as usize, z - z_len as usize)
+    }
+
+    /// Returns the corner of the brick with the largest X, Y and Z,
+    /// before it has been offset.
+    fn end(&self) -> (usize, usize, usize) {
+        (
+            self.position.0 as usize,
+            self.position.1 as usize,
+            self.position.2 as usize,
+        )
+    }
 
-        let size_offset = self.size as f32 / 4.0;
+    /// Returns the brick centered on its position, where anchor is
+    /// the point of the voxel grid that ends up at 0, and the brick
+    /// is floored when its bottom ends up on the ground.
+    pub(crate) fn calculate_right_offset(&self, anchor: (f32, f32, f32)) -> Brick {
+        let (x_len, y_len, z_len) = self.dimensions();
+
+        let x = right_to_center_coord(self.position.0 - anchor.0, x_len);
+        let y = right_to_center_coord(self.position.1 - anchor.1, y_len);
+        let z = right_to_center_coord(self.position.2 - anchor.2, z_len);
+        let floored = self.start().2 as f32 == anchor.2;
 
         Brick {
-            position: (
-                (x / 2.0) - min_size_offset,
-                (y / 2.0) - min_size_offset,
-                (self.position.2 / 2.0) - size_offset,
-            ),
+            position: (x / 2.0, y / 2.0, z / 2.0),
             size: self.size,
+            shape: self.shape,
+            orientation: self.orientation,
+            color_id: self.color_id,
+            source_block: self.source_block,
+            name: self.name.clone(),
             floored,
         }
     }
@@ -146,8 +315,8 @@ mod tests {
     #[test]
     fn place_8_4_cube() {
         let mut brick_builder = BrickBuilder::new();
-        brick_builder.with_brick(Brick::new((8, 8, 8), 8));
         brick_builder.with_brick(Brick::new((12, 4, 4), 4));
+        brick_builder.with_brick(Brick::new((8, 8, 8), 8));
 
         let expected = include_str!("../../assets/brick_comparisons/8-4Cube.bls").to_string();
         let actual = to_save_file_output(&brick_builder.build());
@@ -241,8 +410,8 @@ mod tests {
     #[test]
     fn place_16_8_cube() {
         let mut brick_builder = BrickBuilder::new();
-        brick_builder.with_brick(Brick::new((16, 16, 16), 16));
         brick_builder.with_brick(Brick::new((24, 8, 8), 8));
+        brick_builder.with_brick(Brick::new((16, 16, 16), 16));
 
         let expected = include_str!("../../assets/brick_comparisons/16-8Cube.bls").to_string();
         let actual = to_save_file_output(&brick_builder.build());
@@ -253,9 +422,9 @@ mod tests {
     #[test]
     fn place_16_4_8_cube() {
         let mut brick_builder = BrickBuilder::new();
-        brick_builder.with_brick(Brick::new((16, 16, 16), 16));
         brick_builder.with_brick(Brick::new((20, 4, 4), 4));
         brick_builder.with_brick(Brick::new((28, 8, 8), 8));
+        brick_builder.with_brick(Brick::new((16, 16, 16), 16));
 
         let expected = include_str!("../../assets/brick_comparisons/16-4-8Cube.bls").to_string();
         let actual = to_save_file_output(&brick_builder.build());
@@ -503,4 +672,132 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn place_one_4x_ramp() {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Corner);
+        brick_builder.with_brick(Brick::new_oriented(
+            (4, 4, 4),
+            4,
+            BrickShape::Ramp,
+            Facing::NegativeX,
+        ));
+
+        let expected = "4x Ramp\" 1 1 1 2 1 6  0 0 1 1 1";
+        let actual = format!("{}", brick_builder.build()[0]);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn place_rotated_long_ramps() {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Corner);
+        brick_builder.with_brick(Brick::new((4, 4, 4), 4));
+        brick_builder.with_brick(Brick::new_oriented(
+            (12, 4, 4),
+            4,
+            BrickShape::LongRamp,
+            Facing::PositiveX,
+        ));
+        brick_builder.with_brick(Brick::new_oriented(
+            (4, 12, 4),
+            4,
+            BrickShape::LongRamp,
+            Facing::PositiveY,
+        ));
+
+        let expected = vec![
+            "4x Cube\" 1 1 1 0 1 6  0 0 1 1 1",
+            "4x Long Ramp\" 4 1 1 0 1 6  0 0 1 1 1",
+            "4x Long Ramp\" 1 4 1 1 1 6  0 0 1 1 1",
+        ];
+        let actual = brick_builder
+            .build()
+            .iter()
+            .map(|brick| brick.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(expected, actual);
+    }
+
+    /// Returns an 8x cube and a 4x cube beside each other, both
+    /// resting 8 units above the bottom of the voxel grid.
+    fn elevated_bricks(elevation: Elevation) -> Vec<String> {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Corner);
+        brick_builder.with_elevation(elevation);
+        brick_builder.with_brick(Brick::new((8, 8, 16), 8));
+        brick_builder.with_brick(Brick::new((12, 4, 12), 4));
+
+        brick_builder
+            .build()
+            .iter()
+            .map(|brick| brick.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn place_elevated_model_on_ground() {
+        let expected = vec![
+            "8x Cube\" 2 2 2 0 1 6  0 0 1 1 1",
+            "4x Cube\" 5 1 1 0 1 6  0 0 1 1 1",
+        ];
+
+        let actual = elevated_bricks(Elevation::PlaceOnGround);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn keep_elevated_model_off_ground() {
+        let expected = vec![
+            "8x Cube\" 2 2 6 0 0 6  0 0 1 1 1",
+            "4x Cube\" 5 1 5 0 0 6  0 0 1 1 1",
+        ];
+
+        let actual = elevated_bricks(Elevation::KeepOriginal);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn place_nothing_from_empty_model() {
+        let mut brick_builder = BrickBuilder::new();
+
+        assert!(brick_builder.build().is_empty());
+    }
+
+    #[test]
+    fn place_model_around_its_center() {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Center);
+        brick_builder.with_brick(Brick::new((8, 8, 16), 8));
+        brick_builder.with_brick(Brick::new((12, 4, 12), 4));
+
+        let expected = vec![
+            "8x Cube\" -1 0 2 0 1 6  0 0 1 1 1",
+            "4x Cube\" 2 -1 1 0 1 6  0 0 1 1 1",
+        ];
+        let actual = brick_builder
+            .build()
+            .iter()
+            .map(|brick| brick.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn place_model_where_it_was_in_source() {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Source);
+        brick_builder.with_brick(Brick::new((12, 12, 4), 4));
+
+        let expected = "4x Cube\" 5 5 1 0 1 6  0 0 1 1 1";
+        let actual = format!("{}", brick_builder.build()[0]);
+
+        assert_eq!(expected, actual);
+    }
 }
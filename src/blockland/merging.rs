@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use super::catalog::{BrickCatalog, CatalogBounds};
+use super::parsing::{default_colorset, SaveFile, SavedBrick};
+use super::save_file::BlsWriter;
+use super::Brick;
+
+/// How wide each cell of the overlap lookup is, in Blockland units,
+/// which is as wide as an 8x Cube.
+const OVERLAP_CELL: f32 = 4.0;
+/// How far two bricks may reach into each other without counting as
+/// overlapping, to make up for rounding in the positions written to
+/// save files.
+const OVERLAP_TOLERANCE: f32 = 0.01;
+
+fn overlaps((start1, end1): CatalogBounds, (start2, end2): CatalogBounds) -> bool {
+    start1.0 + OVERLAP_TOLERANCE < end2.0
+        && start2.0 + OVERLAP_TOLERANCE < end1.0
+        && start1.1 + OVERLAP_TOLERANCE < end2.1
+        && start2.1 + OVERLAP_TOLERANCE < end1.1
+        && start1.2 + OVERLAP_TOLERANCE < end2.2
+        && start2.2 + OVERLAP_TOLERANCE < end1.2
+}
+
+/// Returns every overlap lookup cell the bounds reach into.
+fn cells((start, end): CatalogBounds) -> impl Iterator<Item = (i64, i64, i64)> {
+    let cell_range = |start: f32, end: f32| {
+        let first = ((start + OVERLAP_TOLERANCE) / OVERLAP_CELL).floor() as i64;
+        let last = ((end - OVERLAP_TOLERANCE) / OVERLAP_CELL).floor() as i64;
+        first..=last.max(first)
+    };
+
+    cell_range(start.0, end.0).flat_map(move |cell_x| {
+        cell_range(start.1, end.1).flat_map(move |cell_y| {
+            cell_range(start.2, end.2).map(move |cell_z| (cell_x, cell_y, cell_z))
+        })
+    })
+}
+
+/// Returns the index of the color of the colorset closest to color.
+fn closest_color(colorset: &[[f32; 4]], color: [f32; 4]) -> Option<usize> {
+    let distance = |other: &[f32; 4]| -> f32 {
+        color
+            .iter()
+            .zip(other)
+            .map(|(channel, other_channel)| (channel - other_channel).powi(2))
+            .sum()
+    };
+
+    colorset
+        .iter()
+        .enumerate()
+        .min_by(|(_, color1), (_, color2)| distance(color1).total_cmp(&distance(color2)))
+        .map(|(color_id, _)| color_id)
+}
+
+/// Returns a save file with the bricks of base_save_file, followed by
+/// the bricks moved offset Blockland units away, along with how many
+/// of the moved bricks overlap bricks that were already there.
+///
+/// The moved bricks are painted with whichever color of the base
+/// colorset is closest to the color they had, and are given the owner
+/// and events bls_writer would write them with where they were moved
+/// to. Bricks of the base save file keep theirs, and are never counted
+/// as overlapping if the catalog does not know them.
+pub fn merge_into(
+    base_save_file: &SaveFile,
+    bricks: &[Brick],
+    offset: (f32, f32, f32),
+    bls_writer: &BlsWriter,
+    brick_catalog: &BrickCatalog,
+) -> (SaveFile, usize) {
+    let mut base_bounds: HashMap<(i64, i64, i64), Vec<CatalogBounds>> = HashMap::new();
+    for saved_brick in &base_save_file.bricks {
+        let Some(bounds) = brick_catalog.bounds_of(saved_brick) else {
+            continue;
+        };
+        for cell in cells(bounds) {
+            base_bounds.entry(cell).or_default().push(bounds);
+        }
+    }
+
+    let colorset = default_colorset();
+    let mut merged_save_file = base_save_file.clone();
+    let mut overlapping_count = 0;
+    for brick in bricks {
+        let mut moved_brick = brick.clone();
+        moved_brick.position = (
+            brick.position.0 + offset.0,
+            brick.position.1 + offset.1,
+            brick.position.2 + offset.2,
+        );
+        let mut saved_brick = SavedBrick::from(&moved_brick);
+        saved_brick.is_baseplate = brick.floored && offset.2 == 0.0;
+        saved_brick.attributes = bls_writer.attributes_of(&moved_brick);
+        if let Some(color_id) = colorset
+            .get(brick.color_id)
+            .and_then(|color| closest_color(&base_save_file.colorset, *color))
+        {
+            saved_brick.color_id = color_id;
+        }
+
+        if let Some(bounds) = brick_catalog.bounds_of(&saved_brick) {
+            let is_overlapping = cells(bounds).any(|cell| {
+                base_bounds.get(&cell).is_some_and(|cell_bounds| {
+                    cell_bounds
+                        .iter()
+                        .any(|base_bounds| overlaps(bounds, *base_bounds))
+                })
+            });
+            if is_overlapping {
+                overlapping_count += 1;
+            }
+        }
+
+        merged_save_file.bricks.push(saved_brick);
+    }
+    merged_save_file.linecount = merged_save_file.bricks.len();
+
+    (merged_save_file, overlapping_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::mapping::{BrickBuilder, Origin};
+    use crate::blockland::ownership::{BrickOwners, OwnedRegion};
+    use crate::blockland::parsing::{parse_save_file, BrickAttribute};
+    use crate::blockland::region::Region;
+
+    fn base_save_file() -> SaveFile {
+        parse_save_file(include_str!("../../assets/brick_comparisons/8xCube.bls")).unwrap()
+    }
+
+    fn one_4x_cube() -> Vec<Brick> {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Corner);
+        brick_builder.with_brick(Brick::new((4, 4, 4), 4));
+
+        brick_builder.build()
+    }
+
+    #[test]
+    fn merge_beside_base() {
+        let base_save_file = base_save_file();
+
+        let (actual, overlapping_count) = merge_into(
+            &base_save_file,
+            &one_4x_cube(),
+            (4.0, 0.0, 0.0),
+            &BlsWriter::default(),
+            &BrickCatalog::new(),
+        );
+
+        assert_eq!(overlapping_count, 0);
+        assert_eq!(actual.linecount, 2);
+        assert_eq!(actual.bricks[0], base_save_file.bricks[0]);
+        assert_eq!(actual.bricks[1].position, (5.0, 1.0, 1.0));
+        assert!(actual.bricks[1].is_baseplate);
+    }
+
+    #[test]
+    fn count_bricks_inside_base() {
+        let (_, overlapping_count) = merge_into(
+            &base_save_file(),
+            &one_4x_cube(),
+            (1.0, 1.0, 0.0),
+            &BlsWriter::default(),
+            &BrickCatalog::new(),
+        );
+
+        assert_eq!(overlapping_count, 1);
+    }
+
+    #[test]
+    fn bricks_on_top_of_base_do_not_overlap() {
+        let (actual, overlapping_count) = merge_into(
+            &base_save_file(),
+            &one_4x_cube(),
+            (1.0, 1.0, 4.0),
+            &BlsWriter::default(),
+            &BrickCatalog::new(),
+        );
+
+        assert_eq!(overlapping_count, 0);
+        assert!(!actual.bricks[1].is_baseplate);
+    }
+
+    #[test]
+    fn remap_colors_into_base_colorset() {
+        let mut base_save_file = base_save_file();
+        base_save_file.colorset = vec![[1.0, 1.0, 1.0, 1.0], [0.5, 0.5, 0.5, 1.0]];
+
+        let (actual, _) = merge_into(
+            &base_save_file,
+            &one_4x_cube(),
+            (4.0, 0.0, 0.0),
+            &BlsWriter::default(),
+            &BrickCatalog::new(),
+        );
+
+        assert_eq!(actual.colorset, base_save_file.colorset);
+        assert_eq!(actual.bricks[1].color_id, 1);
+    }
+
+    #[test]
+    fn give_moved_bricks_to_region_owners() {
+        let base_save_file = base_save_file();
+        let bls_writer = BlsWriter {
+            brick_owners: BrickOwners::unowned().with_region(OwnedRegion {
+                region: Region {
+                    start: (4.0, 0.0, 0.0),
+                    end: (8.0, 4.0, 4.0),
+                },
+                owner_id: 1234,
+            }),
+            ..BlsWriter::default()
+        };
+        let mut bricks = one_4x_cube();
+        bricks.extend(one_4x_cube());
+        bricks[1].position.0 += 4.0;
+
+        let (actual, _) = merge_into(
+            &base_save_file,
+            &bricks,
+            (4.0, 0.0, 0.0),
+            &bls_writer,
+            &BrickCatalog::new(),
+        );
+
+        assert_eq!(actual.bricks[0], base_save_file.bricks[0]);
+        assert_eq!(
+            actual.bricks[1].attributes,
+            vec![BrickAttribute::Owner(1234)]
+        );
+        assert!(actual.bricks[2].attributes.is_empty());
+    }
+}
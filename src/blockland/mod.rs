@@ -1,24 +1,112 @@
+pub mod catalog;
+pub mod events;
 pub mod mapping;
+pub mod merging;
+pub mod naming;
+pub mod ordering;
+pub mod ownership;
+pub mod parsing;
+pub mod region;
 pub mod save_file;
+pub mod splitting;
+pub mod voxelizing;
 
 use std::fmt::Display;
 
+use crate::slopes::Facing;
+
+/// The kinds of bricks that can be placed, where anything other than
+/// a Cube requires an add-on providing bricks of that name.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BrickShape {
+    Cube,
+    /// A ramp as large as a cube of the same size.
+    Ramp,
+    /// A ramp twice as long as it is wide and tall.
+    LongRamp,
+}
+
+impl BrickShape {
+    pub fn ui_name(&self, size: u16) -> String {
+        match self {
+            BrickShape::Cube => format!("{}x Cube", size),
+            BrickShape::Ramp => format!("{}x Ramp", size),
+            BrickShape::LongRamp => format!("{}x Long Ramp", size),
+        }
+    }
+
+    /// Returns the length along X, width along Y and height along Z
+    /// of the shape when placed at angle 0.
+    pub fn dimensions(&self, size: u16) -> (u16, u16, u16) {
+        match self {
+            BrickShape::Cube | BrickShape::Ramp => (size, size, size),
+            BrickShape::LongRamp => (size * 2, size, size),
+        }
+    }
+}
+
+/// The color bricks are painted with unless told otherwise, which
+/// is gray in the default colorset.
+pub const DEFAULT_COLOR_ID: usize = 6;
+
 #[derive(Clone)]
 pub struct Brick {
     pub position: (f32, f32, f32),
     pub size: u16,
+    pub shape: BrickShape,
+    /// Where the front of the brick points, which is +X at angle 0.
+    /// Ramps rise toward their front.
+    pub orientation: Facing,
+    /// Which color of the save file's colorset the brick is painted
+    /// with.
+    pub color_id: usize,
+    /// The Minecraft block ID the brick was converted from, for bricks
+    /// that stand in for a single block such as a door.
+    pub source_block: Option<u8>,
+    /// The name the brick is given unless a naming rule names it,
+    /// such as the name both halves of a door share.
+    pub name: Option<String>,
     floored: bool,
 }
 
+impl Brick {
+    /// Returns the Blockland angle ID, where each following angle
+    /// turns the brick another 90 degrees from +X toward +Y.
+    pub fn angle_id(&self) -> usize {
+        match self.orientation {
+            Facing::PositiveX => 0,
+            Facing::PositiveY => 1,
+            Facing::NegativeX => 2,
+            Facing::NegativeY => 3,
+        }
+    }
+
+    /// Returns the size of the brick along X, Y and Z once rotated.
+    pub fn dimensions(&self) -> (u16, u16, u16) {
+        let (length, width, height) = self.shape.dimensions(self.size);
+
+        match self.orientation {
+            Facing::PositiveX | Facing::NegativeX => (length, width, height),
+            Facing::PositiveY | Facing::NegativeY => (width, length, height),
+        }
+    }
+}
+
 impl Display for Brick {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let cube_name = format!("{}x Cube", self.size);
+        let brick_name = self.shape.ui_name(self.size);
         let z_idx = format!("{}", self.position.2);
 
         write!(
             f,
-            "{}\" {} {} {} 0 {} 6  0 0 1 1 1",
-            cube_name, self.position.0, self.position.1, z_idx, self.floored as usize
+            "{}\" {} {} {} {} {} {}  0 0 1 1 1",
+            brick_name,
+            self.position.0,
+            self.position.1,
+            z_idx,
+            self.angle_id(),
+            self.floored as usize,
+            self.color_id
         )
     }
 }
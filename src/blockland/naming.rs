@@ -0,0 +1,97 @@
+use super::events::BrickMatcher;
+use super::Brick;
+
+/// A name to give every brick a matcher finds, which scripts and
+/// events can then look the bricks up by.
+#[derive(PartialEq, Debug, Clone)]
+pub struct NamingRule {
+    pub matcher: BrickMatcher,
+    /// The name as written to the save file, which starts with an
+    /// underscore.
+    pub name: String,
+}
+
+impl NamingRule {
+    /// Returns the rule written as a matcher and a name separated by a
+    /// colon, such as `region=0,0,0,4,4,8:door1`, where the name is
+    /// made up of letters, digits and underscores.
+    ///
+    /// Blockland names start with an underscore, which is added when
+    /// the name is missing one.
+    pub fn parse(rule: &str) -> Option<NamingRule> {
+        let (matcher, name) = rule.rsplit_once(':')?;
+        let name = name.trim();
+        let is_valid = name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_');
+        if !is_valid || name.trim_start_matches('_').is_empty() {
+            return None;
+        }
+
+        let name = if name.starts_with('_') {
+            name.to_string()
+        } else {
+            format!("_{}", name)
+        };
+
+        Some(NamingRule {
+            matcher: BrickMatcher::parse(matcher)?,
+            name,
+        })
+    }
+}
+
+/// Returns the name of the first rule matching the brick, if any.
+pub fn name_for<'a>(naming_rules: &'a [NamingRule], brick: &Brick) -> Option<&'a str> {
+    naming_rules
+        .iter()
+        .find(|naming_rule| naming_rule.matcher.matches(brick))
+        .map(|naming_rule| naming_rule.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::region::Region;
+
+    #[test]
+    fn parse_naming_rule() {
+        let expected = NamingRule {
+            matcher: BrickMatcher::Region(Region {
+                start: (0.0, 0.0, 0.0),
+                end: (4.0, 4.0, 8.0),
+            }),
+            name: "_door1".to_string(),
+        };
+
+        assert_eq!(
+            NamingRule::parse("region=0,0,0,4,4,8:door1"),
+            Some(expected)
+        );
+        assert_eq!(
+            NamingRule::parse("block=64:_door").map(|naming_rule| naming_rule.name),
+            Some("_door".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_invalid_names() {
+        assert!(NamingRule::parse("block=64:front door").is_none());
+        assert!(NamingRule::parse("block=64:_").is_none());
+        assert!(NamingRule::parse("block=64").is_none());
+    }
+
+    #[test]
+    fn first_matching_rule_names_brick() {
+        let mut brick = Brick::new((4, 4, 4), 4);
+        brick.source_block = Some(64);
+        let naming_rules = vec![
+            NamingRule::parse("block=77:button").unwrap(),
+            NamingRule::parse("block=64:door").unwrap(),
+            NamingRule::parse("region=0,0,0,8,8,8:room").unwrap(),
+        ];
+
+        assert_eq!(name_for(&naming_rules, &brick), Some("_door"));
+        assert_eq!(name_for(&naming_rules[..1], &brick), None);
+    }
+}
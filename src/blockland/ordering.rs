@@ -0,0 +1,294 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::Brick;
+
+/// The order bricks are written to a save file in.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum BrickOrder {
+    /// Whatever order the bricks were found in, which depends on the
+    /// decomposer being used.
+    Extraction,
+    /// From the bottom up, by Z, then Y, then X, so that a small
+    /// change to a model only moves the lines of the bricks nearby.
+    #[default]
+    Position,
+    /// Largest bricks first, then by Z, then Y, then X.
+    Size,
+    /// From the bottom up, where every brick comes after the ground
+    /// or a brick it rests on. Bricks with nothing holding them up go
+    /// last.
+    Build,
+}
+
+impl BrickOrder {
+    pub const ALL: [BrickOrder; 4] = [
+        BrickOrder::Extraction,
+        BrickOrder::Position,
+        BrickOrder::Size,
+        BrickOrder::Build,
+    ];
+
+    /// Returns the name used to pick this order from the CLI.
+    pub fn name(&self) -> &str {
+        match self {
+            BrickOrder::Extraction => "extraction",
+            BrickOrder::Position => "position",
+            BrickOrder::Size => "size",
+            BrickOrder::Build => "build",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<BrickOrder> {
+        BrickOrder::ALL
+            .into_iter()
+            .find(|brick_order| brick_order.name() == name)
+    }
+}
+
+fn compare_positions(brick1: &Brick, brick2: &Brick) -> Ordering {
+    brick1
+        .position
+        .2
+        .total_cmp(&brick2.position.2)
+        .then(brick1.position.1.total_cmp(&brick2.position.1))
+        .then(brick1.position.0.total_cmp(&brick2.position.0))
+}
+
+/// How many quarters of a Blockland unit wide each cell of the
+/// support lookup is, which is as wide as a 4x Cube.
+const SUPPORT_CELL: i64 = 8;
+
+/// The space a brick takes up, measured in quarters of a Blockland
+/// unit so that every edge lands on a whole number.
+struct BrickBounds {
+    start: (i64, i64, i64),
+    end: (i64, i64, i64),
+}
+
+impl BrickBounds {
+    fn of(brick: &Brick) -> BrickBounds {
+        let (x_len, y_len, z_len) = brick.dimensions();
+        let center = (
+            (brick.position.0 * 4.0).round() as i64,
+            (brick.position.1 * 4.0).round() as i64,
+            (brick.position.2 * 4.0).round() as i64,
+        );
+
+        BrickBounds {
+            start: (
+                center.0 - x_len as i64,
+                center.1 - y_len as i64,
+                center.2 - z_len as i64,
+            ),
+            end: (
+                center.0 + x_len as i64,
+                center.1 + y_len as i64,
+                center.2 + z_len as i64,
+            ),
+        }
+    }
+
+    fn overlaps_footprint_of(&self, other: &BrickBounds) -> bool {
+        self.start.0 < other.end.0
+            && other.start.0 < self.end.0
+            && self.start.1 < other.end.1
+            && other.start.1 < self.end.1
+    }
+
+    /// Returns every support lookup cell the footprint touches.
+    fn cells(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (start, end) = (self.start, self.end);
+
+        (start.0.div_euclid(SUPPORT_CELL)..=(end.0 - 1).div_euclid(SUPPORT_CELL)).flat_map(
+            move |cell_x| {
+                (start.1.div_euclid(SUPPORT_CELL)..=(end.1 - 1).div_euclid(SUPPORT_CELL))
+                    .map(move |cell_y| (cell_x, cell_y))
+            },
+        )
+    }
+}
+
+fn compare_bottoms(brick1: &Brick, brick2: &Brick) -> Ordering {
+    let (bounds1, bounds2) = (BrickBounds::of(brick1), BrickBounds::of(brick2));
+
+    bounds1
+        .start
+        .2
+        .cmp(&bounds2.start.2)
+        .then_with(|| compare_positions(brick1, brick2))
+}
+
+/// Returns whether each brick can be reached from the ground by
+/// going up from brick to brick, where the bricks are already
+/// sorted from the lowest bottom up.
+fn find_supported(bricks: &[Brick]) -> Vec<bool> {
+    // Supported bricks, looked up by the height of their top and
+    // which cells their footprint covers.
+    let mut supporting_tops: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+    let bounds = bricks.iter().map(BrickBounds::of).collect::<Vec<_>>();
+    let mut supported = vec![false; bricks.len()];
+
+    for (brick_idx, brick) in bricks.iter().enumerate() {
+        let brick_bounds = &bounds[brick_idx];
+        supported[brick_idx] = brick.floored
+            || brick_bounds.cells().any(|(cell_x, cell_y)| {
+                supporting_tops
+                    .get(&(brick_bounds.start.2, cell_x, cell_y))
+                    .is_some_and(|below| {
+                        below
+                            .iter()
+                            .any(|below_idx| bounds[*below_idx].overlaps_footprint_of(brick_bounds))
+                    })
+            });
+
+        if !supported[brick_idx] {
+            continue;
+        }
+        for (cell_x, cell_y) in brick_bounds.cells() {
+            supporting_tops
+                .entry((brick_bounds.end.2, cell_x, cell_y))
+                .or_default()
+                .push(brick_idx);
+        }
+    }
+
+    supported
+}
+
+/// Returns the bricks in build order, along with how many of the
+/// bricks at the end have nothing holding them up.
+pub fn sort_for_building(mut bricks: Vec<Brick>) -> (Vec<Brick>, usize) {
+    bricks.sort_by(compare_bottoms);
+    let supported = find_supported(&bricks);
+
+    let (mut supported_bricks, floating_bricks): (Vec<_>, Vec<_>) = bricks
+        .into_iter()
+        .zip(supported)
+        .partition(|(_, is_supported)| *is_supported);
+    let floating_count = floating_bricks.len();
+    supported_bricks.extend(floating_bricks);
+
+    let bricks = supported_bricks
+        .into_iter()
+        .map(|(brick, _)| brick)
+        .collect();
+    (bricks, floating_count)
+}
+
+/// Puts the bricks in the given order. Bricks that tie keep the
+/// order they were in, so the result is the same on every run.
+pub fn sort_bricks(bricks: &mut Vec<Brick>, brick_order: BrickOrder) {
+    match brick_order {
+        BrickOrder::Extraction => {}
+        BrickOrder::Position => bricks.sort_by(compare_positions),
+        BrickOrder::Size => bricks.sort_by(|brick1, brick2| {
+            brick2
+                .size
+                .cmp(&brick1.size)
+                .then_with(|| compare_positions(brick1, brick2))
+        }),
+        BrickOrder::Build => {
+            let (build_order, _) = sort_for_building(std::mem::take(bricks));
+            *bricks = build_order;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::mapping::BrickBuilder;
+
+    fn scattered_bricks() -> Vec<Brick> {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_brick(Brick::new((8, 4, 8), 4));
+        brick_builder.with_brick(Brick::new((16, 16, 16), 8));
+        brick_builder.with_brick(Brick::new((4, 8, 4), 4));
+        brick_builder.with_brick(Brick::new((4, 4, 4), 4));
+
+        brick_builder.build()
+    }
+
+    fn positions_of(bricks: &[Brick]) -> Vec<(f32, f32, f32)> {
+        bricks.iter().map(|brick| brick.position).collect()
+    }
+
+    #[test]
+    fn position_order_goes_bottom_up() {
+        let mut bricks = scattered_bricks();
+
+        sort_bricks(&mut bricks, BrickOrder::Position);
+
+        let expected = vec![
+            (0.0, 0.0, 1.0),
+            (0.0, 2.0, 1.0),
+            (2.0, 0.0, 3.0),
+            (5.0, 5.0, 6.0),
+        ];
+        assert_eq!(expected, positions_of(&bricks));
+    }
+
+    #[test]
+    fn size_order_puts_largest_first() {
+        let mut bricks = scattered_bricks();
+
+        sort_bricks(&mut bricks, BrickOrder::Size);
+
+        let sizes = bricks.iter().map(|brick| brick.size).collect::<Vec<u16>>();
+        assert_eq!(sizes, vec![8, 4, 4, 4]);
+        assert_eq!(bricks[1].position, (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn extraction_order_is_untouched() {
+        let mut bricks = scattered_bricks();
+        let expected = positions_of(&bricks);
+
+        sort_bricks(&mut bricks, BrickOrder::Extraction);
+
+        assert_eq!(expected, positions_of(&bricks));
+    }
+
+    #[test]
+    fn build_order_puts_support_first() {
+        let mut brick_builder = BrickBuilder::new();
+        // A 4x cube resting on top of an 8x cube, listed first.
+        brick_builder.with_brick(Brick::new((8, 8, 12), 4));
+        brick_builder.with_brick(Brick::new((8, 8, 8), 8));
+        let bricks = brick_builder.build();
+
+        let (actual, floating_count) = sort_for_building(bricks);
+
+        let sizes = actual.iter().map(|brick| brick.size).collect::<Vec<u16>>();
+        assert_eq!(sizes, vec![8, 4]);
+        assert_eq!(floating_count, 0);
+    }
+
+    #[test]
+    fn build_order_reports_floating_bricks() {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_brick(Brick::new((4, 4, 4), 4));
+        // Stacked on each other, but off to the side and above the
+        // ground with nothing beneath them.
+        brick_builder.with_brick(Brick::new((12, 4, 12), 4));
+        brick_builder.with_brick(Brick::new((12, 4, 16), 4));
+        // Touches the floating stack only from the side.
+        brick_builder.with_brick(Brick::new((8, 4, 12), 4));
+        let bricks = brick_builder.build();
+
+        let (actual, floating_count) = sort_for_building(bricks);
+
+        assert_eq!(floating_count, 3);
+        assert_eq!(actual[0].position, (0.0, 0.0, 1.0));
+        assert!(actual[1..].iter().all(|brick| !brick.floored));
+    }
+
+    #[test]
+    fn orders_by_name() {
+        for brick_order in BrickOrder::ALL {
+            assert_eq!(BrickOrder::named(brick_order.name()), Some(brick_order));
+        }
+        assert_eq!(BrickOrder::named("random"), None);
+    }
+}
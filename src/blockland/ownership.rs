@@ -0,0 +1,103 @@
+use super::region::Region;
+use super::save_file::PUBLIC_OWNER_ID;
+
+/// A region whose bricks belong to one BL_ID.
+#[derive(PartialEq, Debug, Clone)]
+pub struct OwnedRegion {
+    pub region: Region,
+    pub owner_id: u32,
+}
+
+/// Who the bricks written to a save file belong to.
+#[derive(PartialEq, Debug, Clone)]
+pub struct BrickOwners {
+    /// The BL_ID of bricks outside of every region, or None to write
+    /// them without an owner.
+    pub default_owner: Option<u32>,
+    /// Regions whose bricks belong to someone other than the default
+    /// owner, where the first region a brick is in wins.
+    pub regions: Vec<OwnedRegion>,
+}
+
+impl BrickOwners {
+    /// Returns owners where every brick belongs to owner_id.
+    pub fn owned_by(owner_id: u32) -> BrickOwners {
+        BrickOwners {
+            default_owner: Some(owner_id),
+            regions: Vec::new(),
+        }
+    }
+
+    /// Returns owners where no brick has an owner, so that whoever
+    /// loads the save file is left to decide.
+    pub fn unowned() -> BrickOwners {
+        BrickOwners {
+            default_owner: None,
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn with_region(mut self, owned_region: OwnedRegion) -> BrickOwners {
+        self.regions.push(owned_region);
+        self
+    }
+
+    /// Returns the BL_ID of whoever owns a brick centered at position.
+    pub fn owner_of(&self, position: (f32, f32, f32)) -> Option<u32> {
+        self.regions
+            .iter()
+            .find(|owned_region| owned_region.region.contains(position))
+            .map_or(self.default_owner, |owned_region| {
+                Some(owned_region.owner_id)
+            })
+    }
+}
+
+/// Every brick belongs to the public BL_ID, which lets anyone build
+/// on and modify them.
+impl Default for BrickOwners {
+    fn default() -> Self {
+        BrickOwners::owned_by(PUBLIC_OWNER_ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plot_of(owner_id: u32) -> OwnedRegion {
+        OwnedRegion {
+            region: Region {
+                start: (0.0, 0.0, 0.0),
+                end: (16.0, 16.0, 100.0),
+            },
+            owner_id,
+        }
+    }
+
+    #[test]
+    fn bricks_belong_to_public_by_default() {
+        let brick_owners = BrickOwners::default();
+
+        assert_eq!(brick_owners.owner_of((5.0, 1.0, 1.0)), Some(999999));
+    }
+
+    #[test]
+    fn bricks_in_region_belong_to_its_owner() {
+        let brick_owners = BrickOwners::owned_by(1234).with_region(plot_of(5678));
+
+        assert_eq!(brick_owners.owner_of((5.0, 1.0, 1.0)), Some(5678));
+        assert_eq!(brick_owners.owner_of((17.0, 1.0, 1.0)), Some(1234));
+        assert_eq!(brick_owners.owner_of((16.0, 1.0, 1.0)), Some(1234));
+    }
+
+    #[test]
+    fn first_region_wins() {
+        let brick_owners = BrickOwners::unowned()
+            .with_region(plot_of(5678))
+            .with_region(plot_of(1234));
+
+        assert_eq!(brick_owners.owner_of((5.0, 1.0, 1.0)), Some(5678));
+        assert_eq!(brick_owners.owner_of((-5.0, 1.0, 1.0)), None);
+    }
+}
@@ -0,0 +1,615 @@
+use std::fmt::Display;
+
+use super::save_file::{DEFAULT_COLORSET, PUBLIC_OWNER_ID, SAVE_WARNING};
+use super::{Brick, BrickShape};
+use crate::slopes::Facing;
+
+/// Why a save file could not be read.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ParseError {
+    /// The save file ended before the given part of it was found.
+    UnexpectedEnd(&'static str),
+    /// The line at line_number, counting from 1, is not what was
+    /// expected at that point of the save file.
+    InvalidLine {
+        line_number: usize,
+        reason: &'static str,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedEnd(missing) => {
+                write!(f, "Save file ended before its {}.", missing)
+            }
+            ParseError::InvalidLine {
+                line_number,
+                reason,
+            } => write!(f, "Line {} of save file: {}.", line_number, reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An event on a brick, as set up in the Wrench's event editor.
+#[derive(PartialEq, Debug, Clone)]
+pub struct BrickEvent {
+    pub index: usize,
+    pub enabled: bool,
+    pub input_event: String,
+    /// How long to wait before running the output event, in
+    /// milliseconds.
+    pub delay: u32,
+    pub target: String,
+    /// The name of the brick targeted when target is a named brick.
+    pub named_target: String,
+    pub output_event: String,
+    pub parameters: Vec<String>,
+}
+
+/// One of the `+-` lines following a brick line.
+#[derive(PartialEq, Debug, Clone)]
+pub enum BrickAttribute {
+    /// The BL_ID of whoever owns the brick.
+    Owner(u32),
+    Event(BrickEvent),
+    Light {
+        ui_name: String,
+        enabled: bool,
+    },
+    Emitter {
+        ui_name: String,
+        direction: usize,
+    },
+    Item {
+        ui_name: String,
+        position: usize,
+        direction: usize,
+        /// How long the item takes to come back once picked up, in
+        /// milliseconds.
+        respawn_time: u32,
+    },
+    AudioEmitter {
+        ui_name: String,
+    },
+    Vehicle {
+        ui_name: String,
+        recolor: bool,
+    },
+    /// The brick's name, which starts with an underscore.
+    NtObjectName(String),
+}
+
+impl Display for BrickAttribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrickAttribute::Owner(bl_id) => write!(f, "+-OWNER {}", bl_id),
+            BrickAttribute::Event(brick_event) => {
+                write!(
+                    f,
+                    "+-EVENT\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    brick_event.index,
+                    brick_event.enabled as usize,
+                    brick_event.input_event,
+                    brick_event.delay,
+                    brick_event.target,
+                    brick_event.named_target,
+                    brick_event.output_event
+                )?;
+                for parameter in &brick_event.parameters {
+                    write!(f, "\t{}", parameter)?;
+                }
+                Ok(())
+            }
+            BrickAttribute::Light { ui_name, enabled } => {
+                write!(f, "+-LIGHT {}\" {}", ui_name, *enabled as usize)
+            }
+            BrickAttribute::Emitter { ui_name, direction } => {
+                write!(f, "+-EMITTER {}\" {}", ui_name, direction)
+            }
+            BrickAttribute::Item {
+                ui_name,
+                position,
+                direction,
+                respawn_time,
+            } => write!(
+                f,
+                "+-ITEM {}\" {} {} {}",
+                ui_name, position, direction, respawn_time
+            ),
+            BrickAttribute::AudioEmitter { ui_name } => write!(f, "+-AUDIOEMITTER {}\"", ui_name),
+            BrickAttribute::Vehicle { ui_name, recolor } => {
+                write!(f, "+-VEHICLE {}\" {}", ui_name, *recolor as usize)
+            }
+            BrickAttribute::NtObjectName(name) => write!(f, "+-NTOBJECTNAME {}", name),
+        }
+    }
+}
+
+/// A brick line of a save file, along with the attribute lines
+/// following it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SavedBrick {
+    pub ui_name: String,
+    /// Where the center of the brick is, in Blockland units.
+    pub position: (f32, f32, f32),
+    pub angle_id: usize,
+    pub is_baseplate: bool,
+    /// Which color of the colorset the brick is painted with.
+    pub color_id: usize,
+    /// The name of the print on the brick, which is empty for bricks
+    /// without one.
+    pub print: String,
+    pub color_fx: u8,
+    pub shape_fx: u8,
+    pub raycasting: bool,
+    pub collision: bool,
+    pub rendering: bool,
+    pub attributes: Vec<BrickAttribute>,
+}
+
+impl SavedBrick {
+    /// Returns the brick as one this crate can place, or None if it
+    /// is not a Cube, Ramp or Long Ramp.
+    pub fn to_brick(&self) -> Option<Brick> {
+        let (size, shape_name) = self.ui_name.split_once("x ")?;
+        let size = size.parse::<u16>().ok()?;
+        let shape = match shape_name {
+            "Cube" => BrickShape::Cube,
+            "Ramp" => BrickShape::Ramp,
+            "Long Ramp" => BrickShape::LongRamp,
+            _ => return None,
+        };
+        let orientation = match self.angle_id {
+            0 => Facing::PositiveX,
+            1 => Facing::PositiveY,
+            2 => Facing::NegativeX,
+            3 => Facing::NegativeY,
+            _ => return None,
+        };
+
+        Some(Brick {
+            position: self.position,
+            size,
+            shape,
+            orientation,
+            color_id: self.color_id,
+            source_block: None,
+            name: None,
+            floored: self.is_baseplate,
+        })
+    }
+}
+
+impl From<&Brick> for SavedBrick {
+    fn from(brick: &Brick) -> Self {
+        SavedBrick {
+            ui_name: brick.shape.ui_name(brick.size),
+            position: brick.position,
+            angle_id: brick.angle_id(),
+            is_baseplate: brick.floored,
+            color_id: brick.color_id,
+            print: String::new(),
+            color_fx: 0,
+            shape_fx: 0,
+            raycasting: true,
+            collision: true,
+            rendering: true,
+            attributes: vec![BrickAttribute::Owner(PUBLIC_OWNER_ID)],
+        }
+    }
+}
+
+impl Display for SavedBrick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\" {} {} {} {} {} {} {} {} {} {} {} {}\r\n",
+            self.ui_name,
+            self.position.0,
+            self.position.1,
+            self.position.2,
+            self.angle_id,
+            self.is_baseplate as usize,
+            self.color_id,
+            self.print,
+            self.color_fx,
+            self.shape_fx,
+            self.raycasting as usize,
+            self.collision as usize,
+            self.rendering as usize
+        )?;
+        for attribute in &self.attributes {
+            write!(f, "{}\r\n", attribute)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Everything stored in a Blockland save file.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SaveFile {
+    pub description: Vec<String>,
+    /// The red, green, blue and alpha of each color a brick can be
+    /// painted with.
+    pub colorset: Vec<[f32; 4]>,
+    /// How many bricks the save file claims to have, which Blockland
+    /// does not check against the bricks that follow.
+    pub linecount: usize,
+    pub bricks: Vec<SavedBrick>,
+}
+
+impl Display for SaveFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\r\n", SAVE_WARNING)?;
+        write!(f, "{}\r\n", self.description.len())?;
+        for description_line in &self.description {
+            write!(f, "{}\r\n", description_line)?;
+        }
+        for color in &self.colorset {
+            write!(
+                f,
+                "{:.6} {:.6} {:.6} {:.6}\r\n",
+                color[0], color[1], color[2], color[3]
+            )?;
+        }
+        write!(f, "Linecount {}\r\n", self.linecount)?;
+        for brick in &self.bricks {
+            write!(f, "{}", brick)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_line(line_number: usize, reason: &'static str) -> ParseError {
+    ParseError::InvalidLine {
+        line_number,
+        reason,
+    }
+}
+
+fn parse_flag(field: &str) -> Option<bool> {
+    match field {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+fn parse_color(line: &str) -> Option<[f32; 4]> {
+    let mut channels = line.split(' ').map(|channel| channel.parse::<f32>());
+    let color = [
+        channels.next()?.ok()?,
+        channels.next()?.ok()?,
+        channels.next()?.ok()?,
+        channels.next()?.ok()?,
+    ];
+
+    channels.next().is_none().then_some(color)
+}
+
+fn parse_brick(line: &str) -> Option<SavedBrick> {
+    let (ui_name, fields) = line.split_once("\" ")?;
+    let fields = fields.split(' ').collect::<Vec<&str>>();
+    let [x, y, z, angle_id, is_baseplate, color_id, print, color_fx, shape_fx, raycasting, collision, rendering] =
+        fields[..]
+    else {
+        return None;
+    };
+
+    Some(SavedBrick {
+        ui_name: ui_name.to_string(),
+        position: (x.parse().ok()?, y.parse().ok()?, z.parse().ok()?),
+        angle_id: angle_id.parse().ok()?,
+        is_baseplate: parse_flag(is_baseplate)?,
+        color_id: color_id.parse().ok()?,
+        print: print.to_string(),
+        color_fx: color_fx.parse().ok()?,
+        shape_fx: shape_fx.parse().ok()?,
+        raycasting: parse_flag(raycasting)?,
+        collision: parse_flag(collision)?,
+        rendering: parse_flag(rendering)?,
+        attributes: Vec::new(),
+    })
+}
+
+fn parse_event(fields: &str) -> Option<BrickEvent> {
+    let mut fields = fields.split('\t');
+
+    Some(BrickEvent {
+        index: fields.next()?.parse().ok()?,
+        enabled: parse_flag(fields.next()?)?,
+        input_event: fields.next()?.to_string(),
+        delay: fields.next()?.parse().ok()?,
+        target: fields.next()?.to_string(),
+        named_target: fields.next()?.to_string(),
+        output_event: fields.next()?.to_string(),
+        parameters: fields.map(|parameter| parameter.to_string()).collect(),
+    })
+}
+
+/// Splits the value of an attribute naming a datablock into its UI
+/// name and the space separated fields after it.
+fn split_ui_name(value: &str) -> Option<(String, Vec<&str>)> {
+    let (ui_name, fields) = value.split_once('"')?;
+    let fields = fields
+        .split(' ')
+        .filter(|field| !field.is_empty())
+        .collect();
+
+    Some((ui_name.to_string(), fields))
+}
+
+fn parse_attribute(line: &str) -> Option<BrickAttribute> {
+    if let Some(fields) = line.strip_prefix("EVENT\t") {
+        return parse_event(fields).map(BrickAttribute::Event);
+    }
+
+    let (key, value) = line.split_once(' ')?;
+    let attribute = match key {
+        "OWNER" => BrickAttribute::Owner(value.parse().ok()?),
+        "NTOBJECTNAME" => BrickAttribute::NtObjectName(value.to_string()),
+        _ => {
+            let (ui_name, fields) = split_ui_name(value)?;
+            match (key, &fields[..]) {
+                ("LIGHT", [enabled]) => BrickAttribute::Light {
+                    ui_name,
+                    enabled: parse_flag(enabled)?,
+                },
+                ("EMITTER", [direction]) => BrickAttribute::Emitter {
+                    ui_name,
+                    direction: direction.parse().ok()?,
+                },
+                ("ITEM", [position, direction, respawn_time]) => BrickAttribute::Item {
+                    ui_name,
+                    position: position.parse().ok()?,
+                    direction: direction.parse().ok()?,
+                    respawn_time: respawn_time.parse().ok()?,
+                },
+                ("AUDIOEMITTER", []) => BrickAttribute::AudioEmitter { ui_name },
+                ("VEHICLE", [recolor]) => BrickAttribute::Vehicle {
+                    ui_name,
+                    recolor: parse_flag(recolor)?,
+                },
+                _ => return None,
+            }
+        }
+    };
+
+    Some(attribute)
+}
+
+/// Returns the colorset of the save files written by this crate.
+pub fn default_colorset() -> Vec<[f32; 4]> {
+    DEFAULT_COLORSET
+        .iter()
+        .map(|color_line| parse_color(color_line).expect("Default colorset is malformed."))
+        .collect()
+}
+
+/// Returns the text of a save file from its bytes.
+///
+/// Blockland writes save files in Windows-1252, so each byte is kept
+/// as the character with the same number, which lets encode_save_file
+/// write the text back out without losing anything.
+pub fn decode_save_file(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+/// Returns the bytes of save file text read with decode_save_file,
+/// where characters that do not fit in a byte become question marks.
+pub fn encode_save_file(contents: &str) -> Vec<u8> {
+    contents
+        .chars()
+        .map(|character| u8::try_from(character).unwrap_or(b'?'))
+        .collect()
+}
+
+/// Reads the contents of a .bls save file.
+pub fn parse_save_file(contents: &str) -> Result<SaveFile, ParseError> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(line_idx, line)| (line_idx + 1, line));
+
+    lines.next().ok_or(ParseError::UnexpectedEnd("header"))?;
+
+    let (line_number, description_len) = lines
+        .next()
+        .ok_or(ParseError::UnexpectedEnd("description"))?;
+    let description_len = description_len
+        .parse::<usize>()
+        .map_err(|_| invalid_line(line_number, "Expected how many lines of description follow"))?;
+    let mut description = Vec::with_capacity(description_len);
+    for _ in 0..description_len {
+        let (_, description_line) = lines
+            .next()
+            .ok_or(ParseError::UnexpectedEnd("description"))?;
+        description.push(description_line.to_string());
+    }
+
+    let mut colorset = Vec::new();
+    let linecount = loop {
+        let (line_number, line) = lines.next().ok_or(ParseError::UnexpectedEnd("Linecount"))?;
+        if let Some(linecount) = line.strip_prefix("Linecount ") {
+            break linecount
+                .parse::<usize>()
+                .map_err(|_| invalid_line(line_number, "Expected a number of bricks"))?;
+        }
+
+        colorset.push(parse_color(line).ok_or(invalid_line(line_number, "Expected a color"))?);
+    };
+
+    let mut bricks: Vec<SavedBrick> = Vec::new();
+    for (line_number, line) in lines {
+        if let Some(attribute_line) = line.strip_prefix("+-") {
+            let attribute = parse_attribute(attribute_line)
+                .ok_or(invalid_line(line_number, "Unknown or malformed attribute"))?;
+            bricks
+                .last_mut()
+                .ok_or(invalid_line(
+                    line_number,
+                    "Attribute comes before any brick",
+                ))?
+                .attributes
+                .push(attribute);
+        } else if !line.is_empty() {
+            bricks.push(parse_brick(line).ok_or(invalid_line(line_number, "Malformed brick"))?);
+        }
+    }
+
+    Ok(SaveFile {
+        description,
+        colorset,
+        linecount,
+        bricks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::mapping::BrickBuilder;
+    use crate::blockland::save_file::to_save_file_output;
+
+    #[test]
+    fn save_file_bytes_survive_round_trip() {
+        let contents = include_str!("../../assets/brick_comparisons/4xCube.bls");
+        let (header, rest) = contents.split_once("1\r\n\r\n").unwrap();
+        // A description of Café, whose last letter is 0xE9 in
+        // Windows-1252.
+        let mut bytes = format!("{}1\r\nCaf", header).into_bytes();
+        bytes.push(0xE9);
+        bytes.extend_from_slice(format!("\r\n{}", rest).as_bytes());
+
+        let save_file = parse_save_file(&decode_save_file(&bytes)).unwrap();
+
+        assert_eq!(save_file.description, vec!["Café".to_string()]);
+        assert_eq!(encode_save_file(&save_file.to_string()), bytes);
+    }
+
+    #[test]
+    fn parse_fixture() {
+        let contents = include_str!("../../assets/brick_comparisons/8-4Cube.bls");
+
+        let save_file = parse_save_file(contents).unwrap();
+
+        assert_eq!(save_file.description, vec![String::new()]);
+        assert_eq!(save_file.colorset.len(), 64);
+        assert_eq!(save_file.colorset[0], [0.898039, 0.0, 0.0, 1.0]);
+        assert_eq!(save_file.linecount, 2);
+        assert_eq!(save_file.bricks.len(), 2);
+
+        let first_brick = &save_file.bricks[0];
+        assert_eq!(first_brick.ui_name, "4x Cube");
+        assert_eq!(first_brick.position, (3.0, -1.0, 1.0));
+        assert_eq!(first_brick.color_id, 6);
+        assert_eq!(first_brick.print, "");
+        assert_eq!(first_brick.attributes, vec![BrickAttribute::Owner(999999)]);
+    }
+
+    #[test]
+    fn written_bricks_read_back_the_same() {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_brick(Brick::new((8, 8, 8), 8));
+        brick_builder.with_brick(Brick::new_oriented(
+            (12, 16, 4),
+            4,
+            BrickShape::LongRamp,
+            Facing::NegativeY,
+        ));
+        let bricks = brick_builder.build();
+        let contents = to_save_file_output(&bricks);
+
+        let save_file = parse_save_file(&contents).unwrap();
+
+        assert_eq!(save_file.to_string(), contents);
+        let read_bricks = save_file
+            .bricks
+            .iter()
+            .map(|saved_brick| saved_brick.to_brick().unwrap().to_string())
+            .collect::<Vec<String>>();
+        let expected = bricks
+            .iter()
+            .map(|brick| brick.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(expected, read_bricks);
+        assert_eq!(SavedBrick::from(&bricks[1]), save_file.bricks[1]);
+    }
+
+    #[test]
+    fn parse_every_attribute() {
+        let attribute_lines = [
+            "+-OWNER 12345",
+            "+-EVENT\t0\t1\tonActivate\t0\tSelf\t\tsetColor\t5",
+            "+-LIGHT Red Light\" 1",
+            "+-EMITTER Fire\" 0",
+            "+-ITEM Gun\" 2 1 4000",
+            "+-AUDIOEMITTER Rain\"",
+            "+-VEHICLE Jeep\" 1",
+            "+-NTOBJECTNAME _door",
+        ];
+        let contents = format!(
+            "{}\r\n0\r\nLinecount 1\r\n1x1\" 0 0 0.1 0 1 0  0 0 1 1 1\r\n{}\r\n",
+            SAVE_WARNING,
+            attribute_lines.join("\r\n")
+        );
+
+        let save_file = parse_save_file(&contents).unwrap();
+
+        let attributes = &save_file.bricks[0].attributes;
+        assert_eq!(attributes.len(), attribute_lines.len());
+        assert_eq!(
+            attributes[1],
+            BrickAttribute::Event(BrickEvent {
+                index: 0,
+                enabled: true,
+                input_event: "onActivate".to_string(),
+                delay: 0,
+                target: "Self".to_string(),
+                named_target: String::new(),
+                output_event: "setColor".to_string(),
+                parameters: vec!["5".to_string()],
+            })
+        );
+        assert_eq!(
+            attributes[4],
+            BrickAttribute::Item {
+                ui_name: "Gun".to_string(),
+                position: 2,
+                direction: 1,
+                respawn_time: 4000,
+            }
+        );
+        assert_eq!(save_file.bricks[0].to_brick().map(|_| ()), None);
+        assert_eq!(save_file.to_string(), contents);
+    }
+
+    #[test]
+    fn reject_attribute_before_bricks() {
+        let contents = format!("{}\r\n0\r\nLinecount 0\r\n+-OWNER 1\r\n", SAVE_WARNING);
+
+        let parse_error = parse_save_file(&contents).unwrap_err();
+
+        assert_eq!(
+            parse_error,
+            ParseError::InvalidLine {
+                line_number: 4,
+                reason: "Attribute comes before any brick",
+            }
+        );
+    }
+
+    #[test]
+    fn reject_truncated_save_file() {
+        let contents = format!("{}\r\n1\r\n", SAVE_WARNING);
+
+        let parse_error = parse_save_file(&contents).unwrap_err();
+
+        assert_eq!(parse_error, ParseError::UnexpectedEnd("description"));
+    }
+}
@@ -0,0 +1,69 @@
+/// A box in Blockland units, such as a plot of a map.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Region {
+    /// One corner of the box.
+    pub start: (f32, f32, f32),
+    /// The corner of the box opposite of start.
+    pub end: (f32, f32, f32),
+}
+
+impl Region {
+    /// Returns the region between two corners written as six comma
+    /// separated numbers, such as `0,0,0,16,16,100`.
+    pub fn parse(corners: &str) -> Option<Region> {
+        let coordinates = corners
+            .split(',')
+            .map(|coordinate| coordinate.trim().parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .ok()?;
+        let [x1, y1, z1, x2, y2, z2] = coordinates[..] else {
+            return None;
+        };
+
+        Some(Region {
+            start: (x1, y1, z1),
+            end: (x2, y2, z2),
+        })
+    }
+
+    /// Returns whether the position lies within the box, where
+    /// positions on the far sides of the box are left out so that
+    /// regions can be placed side by side.
+    pub fn contains(&self, position: (f32, f32, f32)) -> bool {
+        let between = |start: f32, end: f32, coordinate: f32| {
+            start.min(end) <= coordinate && coordinate < start.max(end)
+        };
+
+        between(self.start.0, self.end.0, position.0)
+            && between(self.start.1, self.end.1, position.1)
+            && between(self.start.2, self.end.2, position.2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_corners_can_be_given_in_any_order() {
+        let region = Region {
+            start: (16.0, 0.0, 100.0),
+            end: (0.0, 16.0, 0.0),
+        };
+
+        assert!(region.contains((5.0, 1.0, 1.0)));
+        assert!(!region.contains((16.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn parse_region_corners() {
+        let expected = Region {
+            start: (0.0, -8.0, 0.0),
+            end: (16.0, 16.0, 100.5),
+        };
+
+        assert_eq!(Region::parse("0,-8,0,16,16,100.5"), Some(expected));
+        assert_eq!(Region::parse("0,0,0,16,16"), None);
+        assert_eq!(Region::parse("0,0,0,16,16,up"), None);
+    }
+}
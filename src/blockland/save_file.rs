@@ -1,98 +1,318 @@
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::events::{events_for, EventRule, OWN_NAME_PLACEHOLDER};
+use super::naming::{name_for, NamingRule};
+use super::ownership::BrickOwners;
+use super::parsing::BrickAttribute;
 use super::Brick;
+use crate::save_writer::SaveWriter;
+
+/// The first line of every save file.
+pub(crate) const SAVE_WARNING: &str =
+    "This is a Blockland save file.  You probably shouldn't modify it cause you'll screw it up.";
+
+/// The BL_ID that bricks are owned by, which lets anyone build on
+/// and modify them.
+pub(crate) const PUBLIC_OWNER_ID: u32 = 999999;
+
+/// The colors bricks can be painted with in the save files written
+/// here, as red, green, blue and alpha.
+pub(crate) const DEFAULT_COLORSET: [&str; 64] = [
+    "0.898039 0.000000 0.000000 1.000000",
+    "0.898039 0.898039 0.000000 1.000000",
+    "0.000000 0.498039 0.247059 1.000000",
+    "0.200000 0.000000 0.800000 1.000000",
+    "0.898039 0.898039 0.898039 1.000000",
+    "0.749020 0.749020 0.749020 1.000000",
+    "0.498039 0.498039 0.498039 1.000000",
+    "0.200000 0.200000 0.200000 1.000000",
+    "0.392157 0.192157 0.000000 1.000000",
+    "0.901961 0.337255 0.078431 1.000000",
+    "0.749020 0.176471 0.482353 1.000000",
+    "0.384314 0.000000 0.113725 1.000000",
+    "0.129412 0.266667 0.266667 1.000000",
+    "0.000000 0.137255 0.329412 1.000000",
+    "0.101961 0.458824 0.764706 1.000000",
+    "1.000000 1.000000 1.000000 1.000000",
+    "0.078431 0.078431 0.078431 1.000000",
+    "1.000000 1.000000 1.000000 0.247059",
+    "0.921569 0.513726 0.674510 1.000000",
+    "1.000000 0.603922 0.419608 1.000000",
+    "1.000000 0.874510 0.611765 1.000000",
+    "0.956863 0.874510 0.784314 1.000000",
+    "0.784314 0.921569 0.486275 1.000000",
+    "0.537255 0.694118 0.549020 1.000000",
+    "0.556863 0.929412 0.956863 1.000000",
+    "0.694118 0.658824 0.901961 1.000000",
+    "0.874510 0.556863 0.956863 1.000000",
+    "0.666667 0.000000 0.000000 0.698039",
+    "1.000000 0.498039 0.000000 0.698039",
+    "0.988235 0.956863 0.000000 0.698039",
+    "0.000000 0.470588 0.192157 0.698039",
+    "0.000000 0.200000 0.639216 0.698039",
+    "0.592157 0.156863 0.392157 0.694118",
+    "0.549020 0.698039 1.000000 0.698039",
+    "0.847059 0.847059 0.847059 0.698039",
+    "0.098039 0.098039 0.098039 0.698039",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+    "1.000000 0.000000 1.000000 0.000000",
+];
+
+/// Stands in for how many bricks are in the save file in description
+/// lines, since that is not always known until the bricks are written.
+pub const BRICK_COUNT_PLACEHOLDER: &str = "{brick_count}";
+
+/// Returns the date days_since_epoch days after 1970-01-01 as
+/// year-month-day.
+pub fn date_of(days_since_epoch: i64) -> String {
+    // Counts in 400 year eras of the Gregorian calendar, starting from
+    // March so that leap days fall at the end of each year.
+    let days = days_since_epoch + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Returns today's date in UTC as year-month-day.
+pub fn today() -> String {
+    let days_since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() / 86400);
+
+    date_of(days_since_epoch as i64)
+}
+
+/// Returns the lines of the description, where any line break within
+/// a line starts a new line, and an empty description is written as a
+/// single empty line.
+fn description_lines(description: &[String]) -> Vec<&str> {
+    let lines: Vec<&str> = description.iter().flat_map(|line| line.lines()).collect();
+    if lines.is_empty() {
+        return vec![""];
+    }
+
+    lines
+}
+
+pub fn save_header(description: &[String]) -> String {
+    let description_lines = description_lines(description);
+    let mut header_body = format!("{}\r\n", description_lines.len());
+    for description_line in description_lines {
+        header_body.push_str(&format!("{}\r\n", description_line));
+    }
 
-pub fn save_header() -> String {
-    let warning_text = "This is a Blockland save file.  You probably shouldn't modify it cause you'll screw it up.";
-    let mut header_body = "1\r\n\r\n".to_string();
-
-    let data_lines = vec![
-        "0.898039 0.000000 0.000000 1.000000",
-        "0.898039 0.898039 0.000000 1.000000",
-        "0.000000 0.498039 0.247059 1.000000",
-        "0.200000 0.000000 0.800000 1.000000",
-        "0.898039 0.898039 0.898039 1.000000",
-        "0.749020 0.749020 0.749020 1.000000",
-        "0.498039 0.498039 0.498039 1.000000",
-        "0.200000 0.200000 0.200000 1.000000",
-        "0.392157 0.192157 0.000000 1.000000",
-        "0.901961 0.337255 0.078431 1.000000",
-        "0.749020 0.176471 0.482353 1.000000",
-        "0.384314 0.000000 0.113725 1.000000",
-        "0.129412 0.266667 0.266667 1.000000",
-        "0.000000 0.137255 0.329412 1.000000",
-        "0.101961 0.458824 0.764706 1.000000",
-        "1.000000 1.000000 1.000000 1.000000",
-        "0.078431 0.078431 0.078431 1.000000",
-        "1.000000 1.000000 1.000000 0.247059",
-        "0.921569 0.513726 0.674510 1.000000",
-        "1.000000 0.603922 0.419608 1.000000",
-        "1.000000 0.874510 0.611765 1.000000",
-        "0.956863 0.874510 0.784314 1.000000",
-        "0.784314 0.921569 0.486275 1.000000",
-        "0.537255 0.694118 0.549020 1.000000",
-        "0.556863 0.929412 0.956863 1.000000",
-        "0.694118 0.658824 0.901961 1.000000",
-        "0.874510 0.556863 0.956863 1.000000",
-        "0.666667 0.000000 0.000000 0.698039",
-        "1.000000 0.498039 0.000000 0.698039",
-        "0.988235 0.956863 0.000000 0.698039",
-        "0.000000 0.470588 0.192157 0.698039",
-        "0.000000 0.200000 0.639216 0.698039",
-        "0.592157 0.156863 0.392157 0.694118",
-        "0.549020 0.698039 1.000000 0.698039",
-        "0.847059 0.847059 0.847059 0.698039",
-        "0.098039 0.098039 0.098039 0.698039",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-        "1.000000 0.000000 1.000000 0.000000",
-    ];
-
-    for data in data_lines {
+    for data in DEFAULT_COLORSET {
         header_body.push_str(&format!("{}\r\n", data));
     }
 
-    format!("{}\r\n{}", warning_text, header_body)
+    format!("{}\r\n{}", SAVE_WARNING, header_body)
+}
+
+pub fn save_linecount(brick_count: usize) -> String {
+    format!("Linecount {}\r\n", brick_count)
+}
+
+pub fn to_save_file_output(bricks: &[Brick]) -> String {
+    let mut save_file_output = Vec::new();
+    BlsWriter::default()
+        .write_bricks(bricks, &mut save_file_output)
+        .expect("schematic2bls: Could not write save data into memory.");
+
+    String::from_utf8(save_file_output).expect("schematic2bls: Save data is not valid UTF-8.")
+}
+
+/// Writes bricks as a Blockland save file painted with the default
+/// colorset.
+#[derive(Default)]
+pub struct BlsWriter {
+    pub brick_owners: BrickOwners,
+    /// The lines shown when picking the save file to load, where
+    /// BRICK_COUNT_PLACEHOLDER is replaced with how many bricks the
+    /// save file holds.
+    pub description: Vec<String>,
+    /// Rules setting up events on the bricks they match.
+    pub event_rules: Vec<EventRule>,
+    /// Rules naming the bricks they match.
+    pub naming_rules: Vec<NamingRule>,
+}
+
+impl BlsWriter {
+    /// Returns the description of a save file holding brick_count
+    /// bricks.
+    pub fn description_of(&self, brick_count: usize) -> Vec<String> {
+        self.description
+            .iter()
+            .map(|line| line.replace(BRICK_COUNT_PLACEHOLDER, &brick_count.to_string()))
+            .collect()
+    }
+
+    /// Writes everything that comes before the bricks of a save file
+    /// holding brick_count bricks.
+    pub fn write_header(&self, writer: &mut dyn Write, brick_count: usize) -> io::Result<()> {
+        writer.write_all(save_header(&self.description_of(brick_count)).as_bytes())?;
+        writer.write_all(save_linecount(brick_count).as_bytes())
+    }
+
+    /// Returns the lines that follow the brick's own line, which are
+    /// its owner, then its name, then its events.
+    ///
+    /// Bricks already named, such as the halves of a door, keep their
+    /// name so that events aimed at it still reach the whole door, and
+    /// naming rules only name the rest.
+    pub fn attributes_of(&self, brick: &Brick) -> Vec<BrickAttribute> {
+        let owner = self
+            .brick_owners
+            .owner_of(brick.position)
+            .map(BrickAttribute::Owner);
+        let name = brick
+            .name
+            .as_deref()
+            .or_else(|| name_for(&self.naming_rules, brick));
+        let events = events_for(&self.event_rules, brick)
+            .into_iter()
+            .map(|mut brick_event| {
+                brick_event.named_target = brick_event
+                    .named_target
+                    .replace(OWN_NAME_PLACEHOLDER, name.unwrap_or_default());
+                BrickAttribute::Event(brick_event)
+            });
+        let name = name.map(|name| BrickAttribute::NtObjectName(name.to_string()));
+
+        owner.into_iter().chain(name).chain(events).collect()
+    }
+
+    pub fn write_brick(&self, writer: &mut dyn Write, brick: &Brick) -> io::Result<()> {
+        write!(writer, "{}\r\n", brick)?;
+        for attribute in self.attributes_of(brick) {
+            write!(writer, "{}\r\n", attribute)?;
+        }
+
+        Ok(())
+    }
 }
 
-pub fn save_bricks(bricks: &Vec<Brick>) -> String {
-    let linecount_line = format!("Linecount {}", bricks.len());
+impl SaveWriter for BlsWriter {
+    fn name(&self) -> &str {
+        "bls"
+    }
 
-    let mut brick_contents = String::new();
-    for brick in bricks {
-        brick_contents.push_str(&format!("{}\r\n", brick));
-        brick_contents.push_str("+-OWNER 999999\r\n");
+    fn extension(&self) -> &str {
+        "bls"
     }
 
-    format!("{}\r\n{}", linecount_line, brick_contents)
+    fn write_bricks(&self, bricks: &[Brick], writer: &mut dyn Write) -> io::Result<()> {
+        self.write_header(writer, bricks.len())?;
+        for brick in bricks {
+            self.write_brick(writer, brick)?;
+        }
+
+        Ok(())
+    }
 }
 
-pub fn to_save_file_output(bricks: &Vec<Brick>) -> String {
-    let save_header = save_header();
-    let save_bricks = save_bricks(bricks);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_from_days_since_epoch() {
+        assert_eq!(date_of(0), "1970-01-01");
+        assert_eq!(date_of(11016), "2000-02-29");
+        assert_eq!(date_of(20745), "2026-10-19");
+    }
+
+    fn description_of(save_header: &str) -> Vec<&str> {
+        let mut lines = save_header.split("\r\n").skip(1);
+        let description_len = lines.next().unwrap().parse::<usize>().unwrap();
+
+        lines.take(description_len).collect()
+    }
+
+    #[test]
+    fn write_empty_description_as_one_line() {
+        let save_header = save_header(&[]);
+
+        assert_eq!(description_of(&save_header), vec![""]);
+    }
+
+    #[test]
+    fn split_description_on_line_breaks() {
+        let description = vec!["castle.schematic\nScale: 4".to_string(), "".to_string()];
+
+        let save_header = save_header(&description);
+
+        assert_eq!(
+            description_of(&save_header),
+            vec!["castle.schematic", "Scale: 4"]
+        );
+    }
+
+    #[test]
+    fn write_name_and_events_after_owner() {
+        let bls_writer = BlsWriter {
+            event_rules: vec![
+                EventRule::parse("color=6:onActivate,0,Self,disappear,5").unwrap(),
+                EventRule::parse("color=5:onActivate,0,Self,disappear,5").unwrap(),
+            ],
+            naming_rules: vec![NamingRule::parse("color=6:wall").unwrap()],
+            ..BlsWriter::default()
+        };
+        let mut brick_lines = Vec::new();
 
-    format!("{}{}", save_header, save_bricks)
+        bls_writer
+            .write_brick(&mut brick_lines, &Brick::new((4, 4, 4), 4))
+            .unwrap();
+
+        let expected = "4x Cube\" 4 4 4 0 1 6  0 0 1 1 1\r\n+-OWNER 999999\r\n+-NTOBJECTNAME _wall\r\n+-EVENT\t0\t1\tonActivate\t0\tSelf\t\tdisappear\t5\r\n";
+        assert_eq!(expected, String::from_utf8(brick_lines).unwrap());
+    }
+
+    #[test]
+    fn fill_in_brick_count() {
+        let bls_writer = BlsWriter {
+            description: vec![format!("Bricks: {}", BRICK_COUNT_PLACEHOLDER)],
+            ..BlsWriter::default()
+        };
+
+        assert_eq!(bls_writer.description_of(12), vec!["Bricks: 12"]);
+    }
 }
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use super::catalog::STUD_WIDTH;
+use super::Brick;
+
+/// How bricks are spread out over several save files.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SaveSplit {
+    /// Up to this many bricks go in each save file, in the order the
+    /// bricks are in.
+    BrickCount(usize),
+    /// Bricks go in the save file of whichever square tile, this many
+    /// studs wide, their center is in.
+    Tiles(usize),
+}
+
+/// One of the save files bricks are split into.
+pub struct SavePart {
+    /// What tells this part apart from the others in its file name.
+    pub label: String,
+    pub bricks: Vec<Brick>,
+}
+
+fn split_by_count(bricks: Vec<Brick>, brick_count: usize) -> Vec<SavePart> {
+    let mut save_parts: Vec<SavePart> = Vec::new();
+    for brick in bricks {
+        match save_parts.last_mut() {
+            Some(save_part) if save_part.bricks.len() < brick_count => save_part.bricks.push(brick),
+            _ => save_parts.push(SavePart {
+                label: (save_parts.len() + 1).to_string(),
+                bricks: vec![brick],
+            }),
+        }
+    }
+
+    save_parts
+}
+
+fn split_by_tiles(bricks: Vec<Brick>, tile_studs: usize) -> Vec<SavePart> {
+    let tile_side = tile_studs as f32 * STUD_WIDTH;
+
+    // Tiles are kept in order by Y, then X, so that the parts come out
+    // the same way every time.
+    let mut tiles: BTreeMap<(i64, i64), Vec<Brick>> = BTreeMap::new();
+    for brick in bricks {
+        let tile = (
+            (brick.position.1 / tile_side).floor() as i64,
+            (brick.position.0 / tile_side).floor() as i64,
+        );
+        tiles.entry(tile).or_default().push(brick);
+    }
+
+    tiles
+        .into_iter()
+        .map(|((tile_y, tile_x), bricks)| SavePart {
+            label: format!("{}_{}", tile_x, tile_y),
+            bricks,
+        })
+        .collect()
+}
+
+/// Returns the bricks spread out over as many save files as needed,
+/// where none of the save files are empty.
+///
+/// Bricks keep their positions, so loading every part on the same
+/// server puts the whole model back together.
+pub fn split_bricks(bricks: Vec<Brick>, save_split: SaveSplit) -> Vec<SavePart> {
+    match save_split {
+        SaveSplit::BrickCount(brick_count) => split_by_count(bricks, brick_count.max(1)),
+        SaveSplit::Tiles(tile_studs) => split_by_tiles(bricks, tile_studs.max(1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::mapping::{BrickBuilder, Origin};
+
+    /// Returns a row of 5 4x cubes along X.
+    fn row_of_cubes() -> Vec<Brick> {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Corner);
+        for cube_idx in 1..=5 {
+            brick_builder.with_brick(Brick::new((cube_idx * 4, 4, 4), 4));
+        }
+
+        brick_builder.build()
+    }
+
+    fn labels_and_lens(save_parts: &[SavePart]) -> Vec<(String, usize)> {
+        save_parts
+            .iter()
+            .map(|save_part| (save_part.label.clone(), save_part.bricks.len()))
+            .collect()
+    }
+
+    #[test]
+    fn split_by_brick_count() {
+        let save_parts = split_bricks(row_of_cubes(), SaveSplit::BrickCount(2));
+
+        let expected = vec![
+            ("1".to_string(), 2),
+            ("2".to_string(), 2),
+            ("3".to_string(), 1),
+        ];
+        assert_eq!(expected, labels_and_lens(&save_parts));
+        assert_eq!(save_parts[1].bricks[0].position, (5.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn split_by_tile() {
+        // Cubes are 4 studs wide, so each tile holds 3 of them.
+        let save_parts = split_bricks(row_of_cubes(), SaveSplit::Tiles(12));
+
+        let expected = vec![("0_0".to_string(), 3), ("1_0".to_string(), 2)];
+        assert_eq!(expected, labels_and_lens(&save_parts));
+        assert_eq!(save_parts[1].bricks[0].position, (7.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn split_nothing() {
+        let save_parts = split_bricks(Vec::new(), SaveSplit::BrickCount(2));
+
+        assert!(save_parts.is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+use super::catalog::{BrickCatalog, CatalogBounds, STUD_WIDTH};
+use super::parsing::SaveFile;
+use crate::model::volume::VoxelGrid;
+
+/// How close to the edge of a voxel a brick may end while still
+/// being treated as ending on it, to make up for rounding in the
+/// positions written to save files.
+const EDGE_TOLERANCE: f32 = 0.001;
+
+/// The space a brick takes up, in voxels of the grid being built.
+struct VoxelBounds {
+    start: (f32, f32, f32),
+    end: (f32, f32, f32),
+}
+
+impl VoxelBounds {
+    fn of((start, end): CatalogBounds, voxel_side: f32) -> VoxelBounds {
+        VoxelBounds {
+            start: (
+                start.0 / voxel_side,
+                start.1 / voxel_side,
+                start.2 / voxel_side,
+            ),
+            end: (end.0 / voxel_side, end.1 / voxel_side, end.2 / voxel_side),
+        }
+    }
+}
+
+/// Returns the voxels along one axis whose centers lie between start
+/// and end, where offset is the voxel that ends up at 0 in the grid.
+fn voxels_between(start: f32, end: f32, offset: i64) -> std::ops::Range<usize> {
+    let first = (start - 0.5 - EDGE_TOLERANCE).ceil() as i64 - offset;
+    let last = (end - 0.5 + EDGE_TOLERANCE).ceil() as i64 - offset;
+
+    first.max(0) as usize..last.max(0) as usize
+}
+
+/// Turns the bricks of a save file back into voxels, where each
+/// voxel is scale studs wide, and returns the grid along with how
+/// many bricks were left out because the catalog does not know them.
+///
+/// A voxel is filled when its center lies within a brick, so bricks
+/// thinner than half a voxel, such as plates, can fall between them.
+/// Ramps are filled in as if they were cubes.
+pub fn save_file_to_3dgrid(
+    save_file: &SaveFile,
+    brick_catalog: &BrickCatalog,
+    scale: u16,
+) -> (VoxelGrid, usize) {
+    let voxel_side = scale as f32 * STUD_WIDTH;
+
+    let mut unknown_count = 0;
+    let mut brick_bounds = Vec::with_capacity(save_file.bricks.len());
+    for saved_brick in &save_file.bricks {
+        match brick_catalog.bounds_of(saved_brick) {
+            Some(bounds) => brick_bounds.push(VoxelBounds::of(bounds, voxel_side)),
+            None => unknown_count += 1,
+        }
+    }
+
+    if brick_bounds.is_empty() {
+        return (VoxelGrid::new(0, 0, 0), unknown_count);
+    }
+
+    let first_voxel = |start: fn(&VoxelBounds) -> f32| {
+        brick_bounds
+            .iter()
+            .map(|bounds| (start(bounds) - 0.5 - EDGE_TOLERANCE).ceil() as i64)
+            .min()
+            .unwrap_or(0)
+    };
+    let last_voxel = |end: fn(&VoxelBounds) -> f32| {
+        brick_bounds
+            .iter()
+            .map(|bounds| (end(bounds) - 0.5 + EDGE_TOLERANCE).ceil() as i64)
+            .max()
+            .unwrap_or(0)
+    };
+    let offset = (
+        first_voxel(|bounds| bounds.start.0),
+        first_voxel(|bounds| bounds.start.1),
+        first_voxel(|bounds| bounds.start.2),
+    );
+    let mut voxel_grid = VoxelGrid::new(
+        (last_voxel(|bounds| bounds.end.0) - offset.0).max(0) as usize,
+        (last_voxel(|bounds| bounds.end.1) - offset.1).max(0) as usize,
+        (last_voxel(|bounds| bounds.end.2) - offset.2).max(0) as usize,
+    );
+
+    for bounds in &brick_bounds {
+        for x in voxels_between(bounds.start.0, bounds.end.0, offset.0) {
+            for y in voxels_between(bounds.start.1, bounds.end.1, offset.1) {
+                for z in voxels_between(bounds.start.2, bounds.end.2, offset.2) {
+                    voxel_grid.set(x, y, z, true);
+                }
+            }
+        }
+    }
+
+    (voxel_grid, unknown_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::parsing::parse_save_file;
+
+    #[test]
+    fn voxelize_cube_tower() {
+        let save_file = parse_save_file(include_str!(
+            "../../assets/brick_comparisons/8xCubesTower.bls"
+        ))
+        .unwrap();
+
+        let (actual, unknown_count) = save_file_to_3dgrid(&save_file, &BrickCatalog::new(), 4);
+
+        let mut expected = VoxelGrid::new(2, 2, 8);
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..8 {
+                    expected.set(x, y, z, true);
+                }
+            }
+        }
+        assert_eq!(unknown_count, 0);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn skip_unknown_bricks() {
+        let mut save_file =
+            parse_save_file(include_str!("../../assets/brick_comparisons/4xCube.bls")).unwrap();
+        save_file.bricks[0].ui_name = "Pine Tree".to_string();
+
+        let (actual, unknown_count) = save_file_to_3dgrid(&save_file, &BrickCatalog::new(), 4);
+
+        assert_eq!(unknown_count, 1);
+        assert!(actual.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+pub mod save_file;
+
+use crate::blockland::mapping::{Elevation, ModelBounds, Origin};
+use crate::blockland::{Brick, DEFAULT_COLOR_ID};
+use crate::decomposition::Cuboid;
+
+/// How many Brickadia units wide one stud is. Microbricks are
+/// measured in the same units along every axis.
+pub const STUD_UNITS: i32 = 10;
+
+/// A box shaped brick of any size, measured in Brickadia units.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Microbrick {
+    /// Where the center of the brick is.
+    pub position: (i32, i32, i32),
+    /// Half of how long the brick is along X, Y and Z.
+    pub half_size: (u32, u32, u32),
+    /// Which color of the colorset the brick is painted with.
+    pub color_id: usize,
+}
+
+impl Microbrick {
+    /// Returns a microbrick filling the box the brick takes up, where
+    /// each Blockland unit is two studs wide.
+    ///
+    /// Brickadia's Y axis points the opposite way of Blockland's, so Y
+    /// is flipped to keep the model from being mirrored.
+    pub fn of(brick: &Brick) -> Microbrick {
+        let (x_len, y_len, z_len) = brick.dimensions();
+        let (x, y, z) = brick.position;
+        let units_of = |position: f32| (position * 2.0 * STUD_UNITS as f32).round() as i32;
+        let half_size = |len: u16| len as u32 * STUD_UNITS as u32 / 2;
+
+        Microbrick {
+            position: (units_of(x), -units_of(y), units_of(z)),
+            half_size: (half_size(x_len), half_size(y_len), half_size(z_len)),
+            color_id: brick.color_id,
+        }
+    }
+}
+
+fn corners_of(cuboid: &Cuboid) -> ((usize, usize, usize), (usize, usize, usize)) {
+    let (x_len, y_len, z_len) = cuboid.dimensions;
+    let end = cuboid.indexes;
+
+    (
+        (
+            end.0 - x_len as usize,
+            end.1 - y_len as usize,
+            end.2 - z_len as usize,
+        ),
+        end,
+    )
+}
+
+/// Returns a microbrick for each cuboid, placed relative to the
+/// bounding box of all of them the same way BrickBuilder places
+/// bricks, where each voxel of the grid is one stud wide.
+///
+/// Brickadia's Y axis points the opposite way of Blockland's, so Y is
+/// flipped to keep the model from being mirrored.
+pub fn cuboids_to_microbricks(
+    cuboids: &[Cuboid],
+    origin: Origin,
+    elevation: Elevation,
+) -> Vec<Microbrick> {
+    let Some(model_bounds) = ModelBounds::of_boxes(cuboids.iter().map(corners_of)) else {
+        return Vec::new();
+    };
+    let anchor = model_bounds.anchor(origin, elevation);
+
+    // Centers are found in half units first, since a box with an odd
+    // length has its center between two units.
+    let center_of = |start: usize, end: usize, anchor: f32| {
+        ((start + end) as i64 - (2.0 * anchor) as i64) * STUD_UNITS as i64 / 2
+    };
+
+    cuboids
+        .iter()
+        .map(|cuboid| {
+            let (start, end) = corners_of(cuboid);
+            let (x_len, y_len, z_len) = cuboid.dimensions;
+            let half_size = |len: u16| len as u32 * STUD_UNITS as u32 / 2;
+
+            Microbrick {
+                position: (
+                    center_of(start.0, end.0, anchor.0) as i32,
+                    -center_of(start.1, end.1, anchor.1) as i32,
+                    center_of(start.2, end.2, anchor.2) as i32,
+                ),
+                half_size: (half_size(x_len), half_size(y_len), half_size(z_len)),
+                color_id: DEFAULT_COLOR_ID,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockland::mapping::BrickBuilder;
+
+    #[test]
+    fn place_cuboids_on_ground_at_corner() {
+        let cuboids = vec![
+            Cuboid {
+                dimensions: (4, 4, 4),
+                indexes: (8, 8, 8),
+            },
+            Cuboid {
+                dimensions: (12, 4, 1),
+                indexes: (20, 8, 5),
+            },
+        ];
+
+        let actual = cuboids_to_microbricks(&cuboids, Origin::Corner, Elevation::PlaceOnGround);
+
+        let expected = vec![
+            Microbrick {
+                position: (20, -20, 20),
+                half_size: (20, 20, 20),
+                color_id: DEFAULT_COLOR_ID,
+            },
+            Microbrick {
+                position: (100, -20, 5),
+                half_size: (60, 20, 5),
+                color_id: DEFAULT_COLOR_ID,
+            },
+        ];
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn place_nothing_from_no_cuboids() {
+        let actual = cuboids_to_microbricks(&[], Origin::Corner, Elevation::PlaceOnGround);
+
+        assert!(actual.is_empty());
+    }
+
+    fn two_cubes_on_ground_at_corner() -> Vec<Brick> {
+        let mut brick_builder = BrickBuilder::new();
+        brick_builder.with_origin(Origin::Corner);
+        brick_builder.with_brick(Brick::new((16, 8, 8), 8));
+        let mut painted_brick = Brick::new((20, 4, 4), 4);
+        painted_brick.color_id = 3;
+        brick_builder.with_brick(painted_brick);
+
+        brick_builder.build()
+    }
+
+    #[test]
+    fn microbricks_fill_bricks() {
+        let actual: Vec<Microbrick> = two_cubes_on_ground_at_corner()
+            .iter()
+            .map(Microbrick::of)
+            .collect();
+
+        let expected = vec![
+            Microbrick {
+                position: (40, -40, 40),
+                half_size: (40, 40, 40),
+                color_id: DEFAULT_COLOR_ID,
+            },
+            Microbrick {
+                position: (100, -20, 20),
+                half_size: (20, 20, 20),
+                color_id: 3,
+            },
+        ];
+        assert_eq!(expected, actual);
+    }
+}
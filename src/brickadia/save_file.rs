@@ -0,0 +1,459 @@
+use std::io::{self, Write};
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use super::{cuboids_to_microbricks, Microbrick};
+use crate::blockland::mapping::{Elevation, Origin};
+use crate::blockland::parsing::default_colorset;
+use crate::blockland::Brick;
+use crate::decomposition::Cuboid;
+use crate::save_writer::SaveWriter;
+
+/// The version of the .brs format written here.
+const SAVE_VERSION: u16 = 10;
+/// The Brickadia build the save file is marked as coming from.
+const GAME_VERSION: i32 = 8156;
+/// The only brick asset used, which can be any size.
+const MICROBRICK_ASSET: &str = "PB_DefaultMicroBrick";
+const PLASTIC_MATERIAL: &str = "BMC_Plastic";
+/// How strongly the material is applied, out of 10.
+const MATERIAL_INTENSITY: u32 = 5;
+/// The orientation of a brick facing up, without being turned.
+const UPRIGHT_ORIENTATION: u32 = 4 << 2;
+const ORIENTATION_COUNT: u32 = 24;
+const AUTHOR_NAME: &str = "schematic2bls";
+
+/// Writes values a few bits at a time, starting from the lowest bit
+/// of each byte, the way Unreal Engine packs bricks.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_count: usize,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.bit_count.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << (self.bit_count % 8);
+        }
+        self.bit_count += 1;
+    }
+
+    fn write_bits(&mut self, value: u32, bit_count: usize) {
+        for bit_idx in 0..bit_count {
+            self.write_bit(value & (1 << bit_idx) != 0);
+        }
+    }
+
+    /// Writes a value below max using only as many bits as max needs.
+    fn write_int(&mut self, value: u32, max: u32) {
+        let mut written = 0u32;
+        let mut mask = 1u32;
+        while written + mask < max && mask != 0 {
+            if value & mask != 0 {
+                self.write_bit(true);
+                written += mask;
+            } else {
+                self.write_bit(false);
+            }
+            mask <<= 1;
+        }
+    }
+
+    /// Writes a value seven bits at a time, each preceded by whether
+    /// more bits follow.
+    fn write_uint_packed(&mut self, mut value: u32) {
+        loop {
+            let low_bits = value & 0b111_1111;
+            value >>= 7;
+            self.write_bit(value != 0);
+            self.write_bits(low_bits, 7);
+            if value == 0 {
+                return;
+            }
+        }
+    }
+
+    fn write_int_packed(&mut self, value: i32) {
+        self.write_uint_packed((value.unsigned_abs() << 1) | (value > 0) as u32);
+    }
+
+    /// Skips ahead to the start of the next byte.
+    fn align(&mut self) {
+        self.bit_count = self.bytes.len() * 8;
+    }
+}
+
+fn write_i32(writer: &mut Vec<u8>, value: i32) {
+    writer.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Writes a string as its length including a null terminator,
+/// followed by its bytes and the null terminator.
+fn write_string(writer: &mut Vec<u8>, value: &str) {
+    write_i32(writer, value.len() as i32 + 1);
+    writer.extend_from_slice(value.as_bytes());
+    writer.push(0);
+}
+
+fn write_strings(writer: &mut Vec<u8>, values: &[&str]) {
+    write_i32(writer, values.len() as i32);
+    for value in values {
+        write_string(writer, value);
+    }
+}
+
+fn write_nil_uuid(writer: &mut Vec<u8>) {
+    writer.extend_from_slice(&[0; 16]);
+}
+
+/// Writes the sizes of the section, followed by the section itself,
+/// compressed with zlib unless that would make it larger.
+fn write_section(writer: &mut Vec<u8>, section: &[u8]) -> io::Result<()> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(section)?;
+    let compressed = encoder.finish()?;
+
+    write_i32(writer, section.len() as i32);
+    if compressed.len() < section.len() {
+        write_i32(writer, compressed.len() as i32);
+        writer.extend_from_slice(&compressed);
+    } else {
+        write_i32(writer, 0);
+        writer.extend_from_slice(section);
+    }
+
+    Ok(())
+}
+
+/// Returns the colorset of Blockland save files as Brickadia stores
+/// colors, which is blue, green, red and alpha bytes.
+fn brickadia_colorset() -> Vec<[u8; 4]> {
+    default_colorset()
+        .iter()
+        .map(|color| {
+            let to_byte = |channel: f32| (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+            [
+                to_byte(color[2]),
+                to_byte(color[1]),
+                to_byte(color[0]),
+                to_byte(color[3]),
+            ]
+        })
+        .collect()
+}
+
+fn write_microbrick(bits: &mut BitWriter, microbrick: &Microbrick, color_count: u32) {
+    bits.align();
+
+    // Every brick uses the first and only brick asset.
+    bits.write_int(0, 2);
+    bits.write_bit(true);
+    bits.write_uint_packed(microbrick.half_size.0);
+    bits.write_uint_packed(microbrick.half_size.1);
+    bits.write_uint_packed(microbrick.half_size.2);
+    bits.write_int_packed(microbrick.position.0);
+    bits.write_int_packed(microbrick.position.1);
+    bits.write_int_packed(microbrick.position.2);
+    bits.write_int(UPRIGHT_ORIENTATION, ORIENTATION_COUNT);
+
+    // Collides with players, weapons, interaction and tools.
+    for _ in 0..4 {
+        bits.write_bit(true);
+    }
+    // Visible.
+    bits.write_bit(true);
+    // Material, physical material and material intensity.
+    bits.write_int(0, 2);
+    bits.write_int(0, 2);
+    bits.write_int(MATERIAL_INTENSITY, 11);
+
+    // Painted with a color of the colorset.
+    bits.write_bit(false);
+    bits.write_int(microbrick.color_id as u32, color_count.max(2));
+    // Owned by nobody in particular.
+    bits.write_uint_packed(0);
+}
+
+/// Returns the contents of a version 10 Brickadia save file (.brs)
+/// holding the microbricks.
+pub fn to_brs_output(microbricks: &[Microbrick]) -> io::Result<Vec<u8>> {
+    let mut output = b"BRS".to_vec();
+    output.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+    write_i32(&mut output, GAME_VERSION);
+
+    let mut header1 = Vec::new();
+    write_string(&mut header1, "Plate");
+    write_string(&mut header1, AUTHOR_NAME);
+    write_string(&mut header1, "Converted by schematic2bls.");
+    write_nil_uuid(&mut header1);
+    write_string(&mut header1, AUTHOR_NAME);
+    write_nil_uuid(&mut header1);
+    // When the save file was made, left unset.
+    header1.extend_from_slice(&[0; 8]);
+    write_i32(&mut header1, microbricks.len() as i32);
+    write_section(&mut output, &header1)?;
+
+    let colorset = brickadia_colorset();
+    let mut header2 = Vec::new();
+    write_strings(&mut header2, &[]);
+    write_strings(&mut header2, &[MICROBRICK_ASSET]);
+    write_i32(&mut header2, colorset.len() as i32);
+    for color in &colorset {
+        header2.extend_from_slice(color);
+    }
+    write_strings(&mut header2, &[PLASTIC_MATERIAL]);
+    // Brick owners, then physical materials.
+    write_i32(&mut header2, 0);
+    write_strings(&mut header2, &[]);
+    write_section(&mut output, &header2)?;
+
+    // No preview image.
+    output.push(0);
+
+    let mut bits = BitWriter::default();
+    for microbrick in microbricks {
+        write_microbrick(&mut bits, microbrick, colorset.len() as u32);
+    }
+    write_section(&mut output, &bits.bytes)?;
+
+    // No components.
+    let mut components = Vec::new();
+    write_i32(&mut components, 0);
+    write_section(&mut output, &components)?;
+
+    Ok(output)
+}
+
+/// Writes Brickadia save files, where each box a Decomposer finds
+/// becomes one microbrick, since microbricks can be any size. Bricks
+/// already split into cubes each become a microbrick of their own.
+#[derive(Default)]
+pub struct BrsWriter;
+
+impl SaveWriter for BrsWriter {
+    fn name(&self) -> &str {
+        "brs"
+    }
+
+    fn extension(&self) -> &str {
+        "brs"
+    }
+
+    fn write_bricks(&self, bricks: &[Brick], writer: &mut dyn Write) -> io::Result<()> {
+        let microbricks: Vec<Microbrick> = bricks.iter().map(Microbrick::of).collect();
+        writer.write_all(&to_brs_output(&microbricks)?)
+    }
+
+    fn writes_cuboids(&self) -> bool {
+        true
+    }
+
+    fn write_cuboids(
+        &self,
+        cuboids: &[Cuboid],
+        origin: Origin,
+        elevation: Elevation,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let microbricks = cuboids_to_microbricks(cuboids, origin, elevation);
+        writer.write_all(&to_brs_output(&microbricks)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decomposition::{Decomposer, GreedyBoxes};
+    use crate::model::volume::VoxelGrid;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    /// Reads back values written by a BitWriter.
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        bit_count: usize,
+    }
+
+    impl BitReader<'_> {
+        fn read_bit(&mut self) -> bool {
+            let bit = self.bytes[self.bit_count / 8] & (1 << (self.bit_count % 8)) != 0;
+            self.bit_count += 1;
+            bit
+        }
+
+        fn read_int(&mut self, max: u32) -> u32 {
+            let (mut value, mut mask) = (0, 1);
+            while value + mask < max && mask != 0 {
+                if self.read_bit() {
+                    value |= mask;
+                }
+                mask <<= 1;
+            }
+            value
+        }
+
+        fn read_uint_packed(&mut self) -> u32 {
+            let (mut value, mut shift) = (0, 0);
+            loop {
+                let has_more = self.read_bit();
+                for bit_idx in 0..7 {
+                    value |= (self.read_bit() as u32) << (shift + bit_idx);
+                }
+                shift += 7;
+                if !has_more {
+                    return value;
+                }
+            }
+        }
+
+        fn read_int_packed(&mut self) -> i32 {
+            let value = self.read_uint_packed();
+            let magnitude = (value >> 1) as i32;
+            if value & 1 == 1 {
+                magnitude
+            } else {
+                -magnitude
+            }
+        }
+    }
+
+    /// Returns the sections of a save file after its first 9 bytes,
+    /// decompressed, skipping the preview byte after the second one.
+    fn sections_of(output: &[u8]) -> Vec<Vec<u8>> {
+        let mut sections = Vec::new();
+        let mut offset = 9;
+        while offset < output.len() {
+            if sections.len() == 2 {
+                offset += 1;
+            }
+            let read_i32 = |offset: usize| {
+                i32::from_le_bytes(output[offset..offset + 4].try_into().unwrap()) as usize
+            };
+            let (uncompressed_len, compressed_len) = (read_i32(offset), read_i32(offset + 4));
+            offset += 8;
+
+            if compressed_len == 0 {
+                sections.push(output[offset..offset + uncompressed_len].to_vec());
+                offset += uncompressed_len;
+            } else {
+                let mut section = Vec::new();
+                ZlibDecoder::new(&output[offset..offset + compressed_len])
+                    .read_to_end(&mut section)
+                    .unwrap();
+                assert_eq!(section.len(), uncompressed_len);
+                sections.push(section);
+                offset += compressed_len;
+            }
+        }
+
+        sections
+    }
+
+    #[test]
+    fn packed_values_read_back() {
+        let mut bits = BitWriter::default();
+        bits.write_int(5, 11);
+        bits.write_uint_packed(300);
+        bits.write_int_packed(-45);
+        bits.align();
+        bits.write_int_packed(7);
+
+        let mut reader = BitReader {
+            bytes: &bits.bytes,
+            bit_count: 0,
+        };
+        assert_eq!(reader.read_int(11), 5);
+        assert_eq!(reader.read_uint_packed(), 300);
+        assert_eq!(reader.read_int_packed(), -45);
+        reader.bit_count = reader.bit_count.next_multiple_of(8);
+        assert_eq!(reader.read_int_packed(), 7);
+    }
+
+    #[test]
+    fn write_microbricks() {
+        let microbricks = vec![
+            Microbrick {
+                position: (20, -20, 20),
+                half_size: (20, 20, 20),
+                color_id: 6,
+            },
+            Microbrick {
+                position: (100, -20, 5),
+                half_size: (60, 20, 5),
+                color_id: 3,
+            },
+        ];
+
+        let output = to_brs_output(&microbricks).unwrap();
+
+        assert_eq!(&output[..5], b"BRS\x0a\x00");
+        let sections = sections_of(&output);
+        assert_eq!(sections.len(), 4);
+
+        let header1 = &sections[0];
+        let brick_count = i32::from_le_bytes(header1[header1.len() - 4..].try_into().unwrap());
+        assert_eq!(brick_count, 2);
+
+        let mut reader = BitReader {
+            bytes: &sections[2],
+            bit_count: 0,
+        };
+        for microbrick in &microbricks {
+            reader.bit_count = reader.bit_count.next_multiple_of(8);
+            assert_eq!(reader.read_int(2), 0);
+            assert!(reader.read_bit());
+            let half_size = (
+                reader.read_uint_packed(),
+                reader.read_uint_packed(),
+                reader.read_uint_packed(),
+            );
+            let position = (
+                reader.read_int_packed(),
+                reader.read_int_packed(),
+                reader.read_int_packed(),
+            );
+            assert_eq!(half_size, microbrick.half_size);
+            assert_eq!(position, microbrick.position);
+            assert_eq!(reader.read_int(ORIENTATION_COUNT), UPRIGHT_ORIENTATION);
+
+            // Collision, visibility and materials.
+            reader.bit_count += 5;
+            reader.read_int(2);
+            reader.read_int(2);
+            assert_eq!(reader.read_int(11), MATERIAL_INTENSITY);
+
+            assert!(!reader.read_bit());
+            assert_eq!(reader.read_int(64), microbrick.color_id as u32);
+            assert_eq!(reader.read_uint_packed(), 0);
+        }
+        assert_eq!(sections[3], vec![0; 4]);
+    }
+    #[test]
+    fn box_of_voxels_becomes_one_brick() {
+        let mut voxel_grid = VoxelGrid::new(3, 1, 1);
+        for x in 0..3 {
+            voxel_grid.set(x, 0, 0, true);
+        }
+        let cuboids = GreedyBoxes.decompose(&voxel_grid, 4);
+
+        let mut output = Vec::new();
+        BrsWriter
+            .write_cuboids(
+                &cuboids,
+                Origin::Corner,
+                Elevation::PlaceOnGround,
+                &mut output,
+            )
+            .unwrap();
+
+        let sections = sections_of(&output);
+        let header1 = &sections[0];
+        let brick_count = i32::from_le_bytes(header1[header1.len() - 4..].try_into().unwrap());
+        assert_eq!(brick_count, 1);
+    }
+}
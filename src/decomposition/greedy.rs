@@ -0,0 +1,167 @@
+use super::Cuboid;
+use crate::largest_cube::mapping::GridSizes;
+use crate::model::volume::VoxelGrid;
+
+struct GreedyGrid<'a> {
+    grid: &'a VoxelGrid,
+    merged: VoxelGrid,
+}
+
+impl GreedyGrid<'_> {
+    /// Returns whether a voxel is filled and not yet part of a box.
+    fn is_free(&self, x: usize, y: usize, z: usize) -> bool {
+        self.grid.get(x, y, z) && !self.merged.get(x, y, z)
+    }
+
+    /// Returns whether every voxel within the given ranges is free.
+    fn is_free_within(
+        &self,
+        x_range: std::ops::Range<usize>,
+        y_range: std::ops::Range<usize>,
+        z_range: std::ops::Range<usize>,
+    ) -> bool {
+        x_range.into_iter().all(|x| {
+            y_range
+                .clone()
+                .all(|y| z_range.clone().all(|z| self.is_free(x, y, z)))
+        })
+    }
+}
+
+/// Returns boxes covering every filled voxel, found by growing each
+/// box from its lowest free voxel as far as it can go along X, then
+/// along Y, then along Z.
+///
+/// Boxes stop growing before any side gets too long to fit in the
+/// dimensions of a Cuboid, and the rest of the run starts a new box.
+pub fn merge_boxes(grid: &VoxelGrid, scale: u16) -> Vec<Cuboid> {
+    let GridSizes {
+        x_len,
+        y_len,
+        z_len,
+    } = grid.size().clone();
+    let longest_run = (u16::MAX / scale.max(1)) as usize;
+    let mut greedy_grid = GreedyGrid {
+        grid,
+        merged: grid.empty_like(),
+    };
+
+    let mut boxes = Vec::new();
+    for k in 0..z_len {
+        for j in 0..y_len {
+            for i in 0..x_len {
+                if !greedy_grid.is_free(i, j, k) {
+                    continue;
+                }
+
+                let mut end_x = i + 1;
+                while end_x < x_len && end_x - i < longest_run && greedy_grid.is_free(end_x, j, k) {
+                    end_x += 1;
+                }
+
+                let mut end_y = j + 1;
+                while end_y < y_len
+                    && end_y - j < longest_run
+                    && greedy_grid.is_free_within(i..end_x, end_y..end_y + 1, k..k + 1)
+                {
+                    end_y += 1;
+                }
+
+                let mut end_z = k + 1;
+                while end_z < z_len
+                    && end_z - k < longest_run
+                    && greedy_grid.is_free_within(i..end_x, j..end_y, end_z..end_z + 1)
+                {
+                    end_z += 1;
+                }
+
+                for x in i..end_x {
+                    for y in j..end_y {
+                        for z in k..end_z {
+                            greedy_grid.merged.set(x, y, z, true);
+                        }
+                    }
+                }
+
+                let scale = scale as usize;
+                let side_length = |run: usize| {
+                    u16::try_from(run * scale).expect("Runs are kept short enough to fit.")
+                };
+                boxes.push(Cuboid {
+                    dimensions: (
+                        side_length(end_x - i),
+                        side_length(end_y - j),
+                        side_length(end_z - k),
+                    ),
+                    indexes: (end_x * scale, end_y * scale, end_z * scale),
+                });
+            }
+        }
+    }
+
+    boxes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_filled_box() {
+        let grid = VoxelGrid::from(vec![vec![vec![true; 2]; 3]; 4]);
+
+        let expected = vec![Cuboid {
+            dimensions: (16, 12, 8),
+            indexes: (16, 12, 8),
+        }];
+
+        let actual = merge_boxes(&grid, 4);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn splits_runs_too_long_for_u16() {
+        let grid = VoxelGrid::from(vec![vec![vec![true; 1]; 1]; 70]);
+
+        let expected = vec![
+            Cuboid {
+                dimensions: (65000, 1000, 1000),
+                indexes: (65000, 1000, 1000),
+            },
+            Cuboid {
+                dimensions: (5000, 1000, 1000),
+                indexes: (70000, 1000, 1000),
+            },
+        ];
+
+        let actual = merge_boxes(&grid, 1000);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn merges_wall_then_floor() {
+        // An L shape: a floor two voxels long, with a wall on one end.
+        let mut grid = vec![vec![vec![false; 2]; 1]; 2];
+        grid[0][0][0] = true;
+        grid[1][0][0] = true;
+        grid[1][0][1] = true;
+        let grid = VoxelGrid::from(grid);
+
+        let expected = vec![
+            Cuboid {
+                dimensions: (2, 1, 1),
+                indexes: (2, 1, 1),
+            },
+            Cuboid {
+                dimensions: (1, 1, 1),
+                indexes: (2, 1, 2),
+            },
+        ];
+
+        let actual = merge_boxes(&grid, 1);
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,242 @@
+pub mod greedy;
+
+use crate::largest_cube::{tiling::get_largest_cubes_tiled, CubeSizes, LargestCube};
+use crate::model::volume::VoxelGrid;
+
+/// A box of filled voxels claimed by a decomposition, where indexes
+/// follow the same convention as a LargestCube.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cuboid {
+    pub dimensions: (u16, u16, u16),
+    pub indexes: (usize, usize, usize),
+}
+
+impl From<LargestCube> for Cuboid {
+    fn from(largest_cube: LargestCube) -> Self {
+        let side_length = largest_cube.side_length;
+
+        Cuboid {
+            dimensions: (side_length, side_length, side_length),
+            indexes: largest_cube.indexes,
+        }
+    }
+}
+
+impl Cuboid {
+    /// Returns cubes that fill this cuboid, using the biggest permitted
+    /// sizes first.
+    ///
+    /// Any part of the cuboid thinner than the smallest permitted size
+    /// that lines up with the scale is filled with cubes as wide as the
+    /// scale.
+    pub fn to_cubes(&self, cube_sizes: &CubeSizes, scale: u16) -> Vec<LargestCube> {
+        let mut cubes = Vec::new();
+        let corner = (
+            self.indexes.0 - self.dimensions.0 as usize,
+            self.indexes.1 - self.dimensions.1 as usize,
+            self.indexes.2 - self.dimensions.2 as usize,
+        );
+
+        fill_with_cubes(&mut cubes, corner, self.dimensions, cube_sizes, scale);
+        cubes
+    }
+}
+
+/// Fills the box starting at corner with as many of the biggest cube
+/// that fits as possible, then fills whatever is left over along each
+/// axis the same way.
+fn fill_with_cubes(
+    cubes: &mut Vec<LargestCube>,
+    corner: (usize, usize, usize),
+    dimensions: (u16, u16, u16),
+    cube_sizes: &CubeSizes,
+    scale: u16,
+) {
+    let (x_len, y_len, z_len) = dimensions;
+    let thinnest = x_len.min(y_len).min(z_len);
+    if thinnest == 0 {
+        return;
+    }
+
+    let side_length = cube_sizes.largest_fitting_or_scale(thinnest, scale);
+
+    let filled = (
+        x_len - x_len % side_length,
+        y_len - y_len % side_length,
+        z_len - z_len % side_length,
+    );
+    let step = side_length as usize;
+    for i in (0..filled.0 as usize).step_by(step) {
+        for j in (0..filled.1 as usize).step_by(step) {
+            for k in (0..filled.2 as usize).step_by(step) {
+                cubes.push(LargestCube {
+                    side_length,
+                    indexes: (
+                        corner.0 + i + step,
+                        corner.1 + j + step,
+                        corner.2 + k + step,
+                    ),
+                });
+            }
+        }
+    }
+
+    fill_with_cubes(
+        cubes,
+        (corner.0 + filled.0 as usize, corner.1, corner.2),
+        (x_len - filled.0, y_len, z_len),
+        cube_sizes,
+        scale,
+    );
+    fill_with_cubes(
+        cubes,
+        (corner.0, corner.1 + filled.1 as usize, corner.2),
+        (filled.0, y_len - filled.1, z_len),
+        cube_sizes,
+        scale,
+    );
+    fill_with_cubes(
+        cubes,
+        (corner.0, corner.1, corner.2 + filled.2 as usize),
+        (filled.0, filled.1, z_len - filled.2),
+        cube_sizes,
+        scale,
+    );
+}
+
+/// A way of breaking a voxel grid down into cuboids that do not
+/// overlap, and together cover every filled voxel.
+///
+/// Cuboids are axis-aligned, and are split into cubes before being
+/// placed as bricks, so only ramps are ever rotated.
+///
+/// Implement this to run another algorithm through the same pipeline
+/// as the ones that come with schematic2bls.
+pub trait Decomposer {
+    /// Returns the name used to pick this decomposer from the CLI.
+    fn name(&self) -> &str;
+
+    fn decompose(&self, voxel_grid: &VoxelGrid, scale: u16) -> Vec<Cuboid>;
+}
+
+/// Claims the largest cubes first, as found by the Maximal Cube
+/// algorithm, splitting the work over a number of threads.
+pub struct MaximalCubes {
+    pub cube_sizes: CubeSizes,
+    pub threads: usize,
+}
+
+impl Decomposer for MaximalCubes {
+    fn name(&self) -> &str {
+        "maximal-cubes"
+    }
+
+    fn decompose(&self, voxel_grid: &VoxelGrid, scale: u16) -> Vec<Cuboid> {
+        get_largest_cubes_tiled(voxel_grid, scale, &self.cube_sizes, self.threads)
+            .into_iter()
+            .map(Cuboid::from)
+            .collect()
+    }
+}
+
+/// Merges voxels into boxes by extending runs along X, then Y, then
+/// Z, as is common in voxel engines.
+pub struct GreedyBoxes;
+
+impl Decomposer for GreedyBoxes {
+    fn name(&self) -> &str {
+        "greedy-boxes"
+    }
+
+    fn decompose(&self, voxel_grid: &VoxelGrid, scale: u16) -> Vec<Cuboid> {
+        greedy::merge_boxes(voxel_grid, scale)
+    }
+}
+
+/// Returns every decomposer that comes with schematic2bls, with the
+/// default one first.
+pub fn built_in_decomposers(cube_sizes: &CubeSizes, threads: usize) -> Vec<Box<dyn Decomposer>> {
+    vec![
+        Box::new(MaximalCubes {
+            cube_sizes: cube_sizes.clone(),
+            threads,
+        }),
+        Box::new(GreedyBoxes),
+    ]
+}
+
+pub fn built_in_decomposer_named(
+    name: &str,
+    cube_sizes: &CubeSizes,
+    threads: usize,
+) -> Option<Box<dyn Decomposer>> {
+    built_in_decomposers(cube_sizes, threads)
+        .into_iter()
+        .find(|decomposer| decomposer.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuboid_to_cubes_fills_box() {
+        let cuboid = Cuboid {
+            dimensions: (12, 8, 4),
+            indexes: (12, 8, 4),
+        };
+
+        let cubes = cuboid.to_cubes(&CubeSizes::default(), 4);
+
+        assert_eq!(cubes.len(), 6);
+        assert!(cubes.iter().all(|cube| cube.side_length == 4));
+    }
+
+    #[test]
+    fn cuboid_to_cubes_falls_back_to_scale() {
+        let cuboid = Cuboid {
+            dimensions: (9, 6, 3),
+            indexes: (9, 6, 3),
+        };
+
+        let cubes = cuboid.to_cubes(&CubeSizes::default(), 3);
+
+        assert_eq!(cubes.len(), 3 * 2);
+        assert!(cubes.iter().all(|cube| cube.side_length == 3));
+    }
+
+    #[test]
+    fn cuboid_to_cubes_prefers_big_cubes() {
+        let cuboid = Cuboid {
+            dimensions: (24, 16, 16),
+            indexes: (28, 20, 16),
+        };
+
+        let mut cubes = cuboid.to_cubes(&CubeSizes::default(), 4);
+        cubes.sort_by_key(|cube| cube.indexes);
+
+        let expected = vec![
+            LargestCube {
+                side_length: 16,
+                indexes: (20, 20, 16),
+            },
+            LargestCube {
+                side_length: 8,
+                indexes: (28, 12, 8),
+            },
+            LargestCube {
+                side_length: 8,
+                indexes: (28, 12, 16),
+            },
+            LargestCube {
+                side_length: 8,
+                indexes: (28, 20, 8),
+            },
+            LargestCube {
+                side_length: 8,
+                indexes: (28, 20, 16),
+            },
+        ];
+        assert_eq!(expected, cubes);
+    }
+}
@@ -1,6 +1,6 @@
 use super::mapping::{idx_1d_from, idx_3d_from, GridReader, GridSizes};
-use super::LargestCube;
-use std::collections::{BinaryHeap, HashSet};
+use super::{CubeSizes, LargestCube};
+use std::collections::BinaryHeap;
 
 #[derive(Eq, PartialEq, Ord, PartialOrd)]
 pub struct LargestCubeEntry {
@@ -8,9 +8,31 @@ pub struct LargestCubeEntry {
     pub idx: usize,
 }
 
+/// The spots of a GridReader already claimed by some Largest Cube,
+/// packed at one bit per spot.
+pub struct VisitedMap {
+    words: Vec<u64>,
+}
+
+impl VisitedMap {
+    pub fn new(spot_count: usize) -> VisitedMap {
+        VisitedMap {
+            words: vec![0; spot_count.div_ceil(u64::BITS as usize)],
+        }
+    }
+
+    pub fn contains(&self, idx: usize) -> bool {
+        self.words[idx / u64::BITS as usize] & (1 << (idx % u64::BITS as usize)) != 0
+    }
+
+    pub fn insert(&mut self, idx: usize) {
+        self.words[idx / u64::BITS as usize] |= 1 << (idx % u64::BITS as usize);
+    }
+}
+
 pub struct BinaryIndexHeap {
     pub heap: BinaryHeap<LargestCubeEntry>,
-    pub visited: HashSet<usize>,
+    pub visited: VisitedMap,
 }
 
 impl BinaryIndexHeap {
@@ -30,7 +52,7 @@ impl BinaryIndexHeap {
 
         BinaryIndexHeap {
             heap,
-            visited: HashSet::new(),
+            visited: VisitedMap::new(grid.data().len()),
         }
     }
 
@@ -39,17 +61,19 @@ impl BinaryIndexHeap {
     }
 
     pub fn has_visited(&self, idx: usize) -> bool {
-        self.visited.contains(&idx)
+        self.visited.contains(idx)
     }
 }
 
-fn nearest_power_of_two(side_length: u16) -> u16 {
-    let power_of_two = f32::log2(side_length as f32).floor();
-
-    f32::powi(2.0, power_of_two as i32) as u16
+pub fn get_largest_cubes(largest_cube_grid: GridReader, scale: u16) -> Vec<LargestCube> {
+    get_largest_cubes_with(largest_cube_grid, scale, &CubeSizes::default())
 }
 
-pub fn get_largest_cubes(largest_cube_grid: GridReader, scale: u16) -> Vec<LargestCube> {
+pub fn get_largest_cubes_with(
+    largest_cube_grid: GridReader,
+    scale: u16,
+    cube_sizes: &CubeSizes,
+) -> Vec<LargestCube> {
     let mut largest_cubes = Vec::new();
     let sizes = largest_cube_grid.size_cloned();
 
@@ -70,18 +94,18 @@ pub fn get_largest_cubes(largest_cube_grid: GridReader, scale: u16) -> Vec<Large
         idx_3d.1 *= scale as usize;
         idx_3d.2 *= scale as usize;
 
-        let clamped_side_length = nearest_power_of_two(largest_cube_size.clamp(1, 64));
         let mut largest_cube = LargestCube {
-            side_length: clamped_side_length,
+            side_length: cube_sizes.largest_fitting_or_scale(largest_cube_size, scale),
             indexes: idx_3d,
         };
 
-        let found_overlap = mark_visited_from(&largest_cube, &sizes, &mut max_heap, scale as usize);
-        if found_overlap {
+        while mark_visited_from(&largest_cube, &sizes, &mut max_heap, scale as usize) {
             // An overlap means that the current size of the Largest
-            // Cube is too big. Knocking it down by our scale guarantees
-            // there will not be another overlap.
-            largest_cube.side_length = nearest_power_of_two(clamped_side_length - scale);
+            // Cube is too big. Knocking it down by at least our scale
+            // and trying again eventually finds a size that does not
+            // overlap, since a cube of our scale is just this one spot.
+            largest_cube.side_length =
+                cube_sizes.largest_fitting_or_scale(largest_cube.side_length - scale, scale);
         }
 
         largest_cubes.push(largest_cube);
@@ -115,40 +139,30 @@ pub fn mark_visited_from(
     // which is wrong, since that will cause an overlap.
     //
     // Because of this situation, we need a way of checking
-    // if there's overlap, and if so, to undo what we just did
-    // and signal to the caller that there was a problem.
-    let mut found_overlap = false;
-    let mut found_indexes = Vec::new();
-    for i in (end_x..=x).rev() {
-        for j in (end_y..=y).rev() {
-            for k in (end_z..=z).rev() {
-                let idx_1d = idx_1d_from(i, j, k, sizes);
-                if max_heap.visited.contains(&idx_1d) {
-                    found_overlap = true;
-                    break;
-                }
-
-                found_indexes.push(idx_1d);
-                max_heap.visited.insert(idx_1d);
-            }
-        }
+    // if there's overlap before marking anything, and if so, to
+    // signal to the caller that there was a problem.
+    let spots = || {
+        (end_z..=z).flat_map(move |k| {
+            (end_y..=y).flat_map(move |j| (end_x..=x).map(move |i| idx_1d_from(i, j, k, sizes)))
+        })
+    };
+
+    if spots().any(|idx_1d| max_heap.visited.contains(idx_1d)) {
+        return true;
     }
 
-    if !found_overlap {
-        return found_overlap;
+    for idx_1d in spots() {
+        max_heap.visited.insert(idx_1d);
     }
 
-    while let Some(visited_idx) = found_indexes.pop() {
-        max_heap.visited.remove(&visited_idx);
-    }
-
-    found_overlap
+    false
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::largest_cube::mapping::grid_to_largest_cubes;
+    use crate::model::volume::VoxelGrid;
 
     #[test]
     fn largest_cube_simple_2x2() {
@@ -163,7 +177,7 @@ mod tests {
             }
         }
 
-        let found_cubes = grid_to_largest_cubes(grid, 1);
+        let found_cubes = grid_to_largest_cubes(&VoxelGrid::from(grid), 1);
 
         let expected = LargestCube {
             side_length: 2,
@@ -174,4 +188,90 @@ mod tests {
 
         assert_eq!(expected, *actual);
     }
+
+    fn filled_grid(side_length: usize) -> VoxelGrid {
+        VoxelGrid::from(vec![
+            vec![vec![true; side_length]; side_length];
+            side_length
+        ])
+    }
+
+    #[test]
+    fn largest_cube_non_power_of_two() {
+        let found_cubes = grid_to_largest_cubes(&filled_grid(3), 1);
+        let cube_sizes = CubeSizes::new(&[1, 3]);
+
+        let expected = vec![LargestCube {
+            side_length: 3,
+            indexes: (3, 3, 3),
+        }];
+
+        let actual = get_largest_cubes_with(found_cubes, 1, &cube_sizes);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn largest_cube_beyond_64() {
+        let scale = 4;
+        let found_cubes = grid_to_largest_cubes(&filled_grid(32), scale);
+        let cube_sizes = CubeSizes::new(&[4, 8, 16, 32, 64, 128]);
+
+        let expected = vec![LargestCube {
+            side_length: 128,
+            indexes: (128, 128, 128),
+        }];
+
+        let actual = get_largest_cubes_with(found_cubes, scale, &cube_sizes);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn largest_cubes_fall_back_to_scale() {
+        // None of the default sizes line up with a scale of 3.
+        let scale = 3;
+        let found_cubes = grid_to_largest_cubes(&filled_grid(2), scale);
+
+        let largest_cubes = get_largest_cubes_with(found_cubes, scale, &CubeSizes::default());
+
+        assert_eq!(largest_cubes.len(), 2 * 2 * 2);
+        assert!(largest_cubes
+            .iter()
+            .all(|largest_cube| largest_cube.side_length == 3));
+    }
+
+    #[test]
+    fn lone_spots_claimed_with_cubes_as_wide_as_scale() {
+        let scale = 4;
+        let mut grid = vec![vec![vec![true; 3]; 3]; 3];
+        grid[2][2][2] = false;
+        let found_cubes = grid_to_largest_cubes(&VoxelGrid::from(grid), scale);
+        let cube_sizes = CubeSizes::new(&[4, 8]);
+
+        let largest_cubes = get_largest_cubes_with(found_cubes, scale, &cube_sizes);
+
+        let claimed_volume: usize = largest_cubes
+            .iter()
+            .map(|largest_cube| (largest_cube.side_length as usize).pow(3))
+            .sum();
+        assert_eq!(claimed_volume, (3 * 3 * 3 - 1) * 4 * 4 * 4);
+        assert!(largest_cubes.iter().all(|largest_cube| cube_sizes
+            .side_lengths()
+            .contains(&largest_cube.side_length)));
+    }
+
+    #[test]
+    fn largest_cubes_never_overlap() {
+        let found_cubes = grid_to_largest_cubes(&filled_grid(7), 1);
+        let cube_sizes = CubeSizes::new(&[1, 3, 6]);
+
+        let largest_cubes = get_largest_cubes_with(found_cubes, 1, &cube_sizes);
+
+        let claimed_volume: usize = largest_cubes
+            .iter()
+            .map(|largest_cube| (largest_cube.side_length as usize).pow(3))
+            .sum();
+        assert_eq!(claimed_volume, 7 * 7 * 7);
+    }
 }
@@ -1,10 +1,13 @@
-#[derive(Clone)]
+use crate::model::volume::VoxelGrid;
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct GridSizes {
     pub x_len: usize,
     pub y_len: usize,
     pub z_len: usize,
 }
 
+#[derive(Clone)]
 pub struct GridReader {
     sizes: GridSizes,
     grid: Vec<u16>,
@@ -62,22 +65,19 @@ impl GridReader {
     }
 }
 
-pub fn grid_to_largest_cubes(grid: Vec<Vec<Vec<bool>>>, scale: u16) -> GridReader {
-    let length = grid.len();
-    let width = grid.first().unwrap_or(&Vec::new()).len();
-    let height = grid
-        .first()
-        .unwrap_or(&Vec::new())
-        .first()
-        .unwrap_or(&Vec::new())
-        .len();
+pub fn grid_to_largest_cubes(grid: &VoxelGrid, scale: u16) -> GridReader {
+    let GridSizes {
+        x_len: length,
+        y_len: width,
+        z_len: height,
+    } = grid.size().clone();
 
     let mut largest_cube = GridReader::new(length + 1, width + 1, height + 1);
 
     for i in 1..=length {
         for j in 1..=width {
             for k in 1..=height {
-                if grid[i - 1][j - 1][k - 1] {
+                if grid.get(i - 1, j - 1, k - 1) {
                     let smallest_prior_cube = largest_cube
                         .get(i, j, k - 1)
                         .min(largest_cube.get(i, j - 1, k - 1))
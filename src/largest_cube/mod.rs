@@ -1,8 +1,69 @@
 pub mod extraction;
 pub mod mapping;
+pub mod tiling;
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct LargestCube {
     pub side_length: u16,
     pub indexes: (usize, usize, usize),
 }
+
+/// The side lengths a Largest Cube is permitted to have, which
+/// should match the cube bricks available on the target server.
+#[derive(PartialEq, Debug, Clone)]
+pub struct CubeSizes {
+    side_lengths: Vec<u16>,
+}
+
+impl CubeSizes {
+    pub fn new(side_lengths: &[u16]) -> CubeSizes {
+        let mut side_lengths = side_lengths
+            .iter()
+            .copied()
+            .filter(|side_length| *side_length > 0)
+            .collect::<Vec<u16>>();
+        side_lengths.sort_unstable();
+        side_lengths.dedup();
+
+        CubeSizes { side_lengths }
+    }
+
+    pub fn side_lengths(&self) -> &[u16] {
+        &self.side_lengths
+    }
+
+    /// Returns the biggest permitted side length, or 0 if there
+    /// are none.
+    pub fn largest(&self) -> u16 {
+        self.side_lengths.last().copied().unwrap_or(0)
+    }
+
+    /// Returns the biggest permitted side length that fits within
+    /// side_length, and lines up with the voxel grid at this scale.
+    pub fn largest_fitting(&self, side_length: u16, scale: u16) -> Option<u16> {
+        self.side_lengths
+            .iter()
+            .rev()
+            .copied()
+            .find(|permitted| *permitted <= side_length && *permitted % scale == 0)
+    }
+
+    /// Returns the biggest permitted side length that fits within
+    /// side_length, or a cube as wide as the scale when none do.
+    ///
+    /// A cube as wide as the scale is a single spot of the voxel grid,
+    /// so falling back to it makes sure nothing is left out. The brick
+    /// may not exist on the target server unless the scale is one of
+    /// the permitted sizes, which the CLI makes sure of.
+    pub fn largest_fitting_or_scale(&self, side_length: u16, scale: u16) -> u16 {
+        self.largest_fitting(side_length, scale).unwrap_or(scale)
+    }
+}
+
+impl Default for CubeSizes {
+    /// The cube bricks that come with Blockland, along with the
+    /// 1x and 2x Cube add-ons.
+    fn default() -> Self {
+        CubeSizes::new(&[1, 2, 4, 8, 16, 32, 64])
+    }
+}
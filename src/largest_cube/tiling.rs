@@ -0,0 +1,223 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use super::extraction::get_largest_cubes_with;
+use super::mapping::{grid_to_largest_cubes, GridSizes};
+use super::{CubeSizes, LargestCube};
+use crate::model::volume::VoxelGrid;
+
+/// How many of the largest permitted cubes fit along each side of
+/// a tile.
+const CUBES_PER_TILE: usize = 4;
+
+/// Returns how many threads to use when none were asked for.
+pub fn default_thread_count() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Returns how many voxels wide each tile is.
+pub fn tile_side_for(cube_sizes: &CubeSizes, scale: u16) -> usize {
+    let largest_side_length = cube_sizes.largest_fitting_or_scale(u16::MAX, scale);
+
+    CUBES_PER_TILE * (largest_side_length / scale) as usize
+}
+
+/// Returns the origin of every tile covering a grid of these sizes,
+/// ordered along X, then Y, then Z.
+fn tile_origins(sizes: &GridSizes, tile_side: usize) -> Vec<(usize, usize, usize)> {
+    let mut origins = Vec::new();
+
+    for z in (0..sizes.z_len).step_by(tile_side) {
+        for y in (0..sizes.y_len).step_by(tile_side) {
+            for x in (0..sizes.x_len).step_by(tile_side) {
+                origins.push((x, y, z));
+            }
+        }
+    }
+
+    origins
+}
+
+/// Returns the Largest Cubes found in one tile, placed back where
+/// the tile sits in the whole grid.
+fn largest_cubes_in_tile(
+    voxel_grid: &VoxelGrid,
+    origin: (usize, usize, usize),
+    tile_sizes: &GridSizes,
+    scale: u16,
+    cube_sizes: &CubeSizes,
+) -> Vec<LargestCube> {
+    if voxel_grid.is_empty_within(origin, tile_sizes) {
+        return Vec::new();
+    }
+    let tile = voxel_grid.crop(origin, tile_sizes);
+
+    let largest_cubes_grid = grid_to_largest_cubes(&tile, scale);
+    let scale = scale as usize;
+
+    get_largest_cubes_with(largest_cubes_grid, scale as u16, cube_sizes)
+        .into_iter()
+        .map(|mut largest_cube| {
+            largest_cube.indexes.0 += origin.0 * scale;
+            largest_cube.indexes.1 += origin.1 * scale;
+            largest_cube.indexes.2 += origin.2 * scale;
+            largest_cube
+        })
+        .collect()
+}
+
+/// Returns the Largest Cubes of the grid, found by splitting it into
+/// tiles a few of the largest permitted cubes wide and running the
+/// Maximal Cube algorithm on each tile over several threads.
+///
+/// Cubes never cross from one tile into another, and tiles are
+/// stitched back together in the same order every time, so the
+/// result does not depend on how many threads were used. Grids that
+/// fit in a single tile come out the same as if they were not tiled.
+pub fn get_largest_cubes_tiled(
+    voxel_grid: &VoxelGrid,
+    scale: u16,
+    cube_sizes: &CubeSizes,
+    threads: usize,
+) -> Vec<LargestCube> {
+    let tile_side = tile_side_for(cube_sizes, scale);
+    let tile_sizes = GridSizes {
+        x_len: tile_side,
+        y_len: tile_side,
+        z_len: tile_side,
+    };
+
+    let origins = tile_origins(voxel_grid.size(), tile_side);
+    let next_tile = AtomicUsize::new(0);
+    let workers = threads.clamp(1, origins.len().max(1));
+
+    let mut tiles_found = thread::scope(|scope| {
+        let handles = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut found = Vec::new();
+                    loop {
+                        let tile_idx = next_tile.fetch_add(1, Ordering::Relaxed);
+                        let Some(origin) = origins.get(tile_idx) else {
+                            break;
+                        };
+
+                        let largest_cubes = largest_cubes_in_tile(
+                            voxel_grid,
+                            *origin,
+                            &tile_sizes,
+                            scale,
+                            cube_sizes,
+                        );
+                        found.push((tile_idx, largest_cubes));
+                    }
+
+                    found
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("schematic2bls: Tile worker panicked."))
+            .collect::<Vec<(usize, Vec<LargestCube>)>>()
+    });
+
+    tiles_found.sort_unstable_by_key(|(tile_idx, _)| *tile_idx);
+    tiles_found
+        .into_iter()
+        .flat_map(|(_, largest_cubes)| largest_cubes)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::largest_cube::extraction::get_largest_cubes;
+
+    /// Returns a grid with a few filled shapes scattered across
+    /// several tiles.
+    fn scattered_grid() -> VoxelGrid {
+        let mut voxel_grid = VoxelGrid::new(20, 13, 9);
+
+        for x in 0..20 {
+            for y in 0..13 {
+                for z in 0..9 {
+                    let filled = (x + 2 * y + 3 * z) % 7 != 0 || (x < 10 && z < 5);
+                    voxel_grid.set(x, y, z, filled);
+                }
+            }
+        }
+
+        voxel_grid
+    }
+
+    #[test]
+    fn same_cubes_for_any_thread_count() {
+        let voxel_grid = scattered_grid();
+        let cube_sizes = CubeSizes::new(&[1, 2]);
+
+        let expected = get_largest_cubes_tiled(&voxel_grid, 1, &cube_sizes, 1);
+
+        for threads in [2, 3, 8] {
+            let actual = get_largest_cubes_tiled(&voxel_grid, 1, &cube_sizes, threads);
+
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn single_tile_matches_untiled() {
+        let voxel_grid = scattered_grid();
+
+        let expected = get_largest_cubes(grid_to_largest_cubes(&voxel_grid, 4), 4);
+
+        let actual = get_largest_cubes_tiled(&voxel_grid, 4, &CubeSizes::default(), 4);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn tiles_cover_every_voxel_once() {
+        let voxel_grid = scattered_grid();
+        let cube_sizes = CubeSizes::new(&[1, 2]);
+
+        let largest_cubes = get_largest_cubes_tiled(&voxel_grid, 1, &cube_sizes, 4);
+
+        let mut claimed = VoxelGrid::new(20, 13, 9);
+        for largest_cube in &largest_cubes {
+            let side_length = largest_cube.side_length as usize;
+            let (end_x, end_y, end_z) = largest_cube.indexes;
+            for x in end_x - side_length..end_x {
+                for y in end_y - side_length..end_y {
+                    for z in end_z - side_length..end_z {
+                        assert!(!claimed.get(x, y, z));
+                        claimed.set(x, y, z, true);
+                    }
+                }
+            }
+        }
+        assert_eq!(claimed, voxel_grid);
+    }
+
+    #[test]
+    fn sparse_world_only_works_on_filled_tiles() {
+        let mut voxel_grid = VoxelGrid::new_sparse(2000, 2000, 256);
+        for x in 1500..1504 {
+            for y in 20..24 {
+                for z in 250..254 {
+                    voxel_grid.set(x, y, z, true);
+                }
+            }
+        }
+
+        let expected = vec![LargestCube {
+            side_length: 16,
+            indexes: (1504 * 4, 24 * 4, 254 * 4),
+        }];
+
+        let actual = get_largest_cubes_tiled(&voxel_grid, 4, &CubeSizes::default(), 2);
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -1,17 +1,45 @@
 pub mod blockland;
+pub mod brickadia;
+pub mod decomposition;
 pub mod largest_cube;
 pub mod model;
+pub mod save_writer;
+pub mod slopes;
+pub mod streaming;
 
 use std::{
     fs::{self, File},
-    io::{Cursor, Write},
+    io::{self, BufWriter, Cursor, Write},
     path::Path,
 };
 
-use blockland::{mapping::BrickBuilder, save_file::to_save_file_output, Brick};
-use largest_cube::{extraction::get_largest_cubes, mapping::grid_to_largest_cubes, LargestCube};
-use model::conversion::schematic_to_3dgrid;
-use nbt::{decode::read_gzip_compound_tag, CompoundTag};
+use blockland::{
+    catalog::BrickCatalog,
+    mapping::{BrickBuilder, Elevation, Origin},
+    merging::merge_into,
+    ordering::{sort_bricks, sort_for_building, BrickOrder},
+    parsing::{decode_save_file, encode_save_file, parse_save_file, SaveFile},
+    save_file::BlsWriter,
+    splitting::{split_bricks, SaveSplit},
+    voxelizing::save_file_to_3dgrid,
+    Brick, BrickShape,
+};
+use decomposition::{Cuboid, Decomposer};
+use largest_cube::{
+    tiling::{default_thread_count, get_largest_cubes_tiled},
+    CubeSizes, LargestCube,
+};
+use model::{
+    conversion::{
+        grid_to_schematic, grid_to_sponge_schematic, schematic_to_3dgrid,
+        schematic_to_shaped_blocks, InteractiveBlock, ShapedBlocks, DOOR_IDS,
+    },
+    filling::fill_enclosed_cavities,
+    volume::VoxelGrid,
+};
+use nbt::{decode::read_gzip_compound_tag, encode::write_gzip_compound_tag, CompoundTag};
+use save_writer::SaveWriter;
+use slopes::{detection::find_stair_steps, Slope};
 
 pub fn load_schematic(model_arg: &Path) -> CompoundTag {
     let mut file_cursor = Cursor::new(
@@ -22,32 +50,311 @@ pub fn load_schematic(model_arg: &Path) -> CompoundTag {
     read_gzip_compound_tag(&mut file_cursor).expect("Could not read given schematic file.")
 }
 
-pub fn parse_grid_from_model(model: CompoundTag) -> Vec<Vec<Vec<bool>>> {
+pub fn parse_grid_from_model(model: CompoundTag) -> VoxelGrid {
     schematic_to_3dgrid(model)
 }
 
-pub fn extract_largest_cubes_from(voxel_grid: Vec<Vec<Vec<bool>>>, scale: u16) -> Vec<LargestCube> {
-    let largest_cubes_grid = grid_to_largest_cubes(voxel_grid, scale);
+pub fn parse_shaped_blocks_from_model(model: &CompoundTag, scale: u16) -> ShapedBlocks {
+    schematic_to_shaped_blocks(model, scale)
+}
+
+pub fn fill_cavities_in(voxel_grid: VoxelGrid) -> VoxelGrid {
+    fill_enclosed_cavities(voxel_grid)
+}
+
+pub fn extract_largest_cubes_from(voxel_grid: VoxelGrid, scale: u16) -> Vec<LargestCube> {
+    extract_largest_cubes_with(voxel_grid, scale, &CubeSizes::default())
+}
 
-    get_largest_cubes(largest_cubes_grid, scale)
+pub fn extract_largest_cubes_with(
+    voxel_grid: VoxelGrid,
+    scale: u16,
+    cube_sizes: &CubeSizes,
+) -> Vec<LargestCube> {
+    get_largest_cubes_tiled(&voxel_grid, scale, cube_sizes, default_thread_count())
+}
+
+pub fn extract_cuboids_with(
+    voxel_grid: &VoxelGrid,
+    scale: u16,
+    decomposer: &dyn Decomposer,
+) -> Vec<Cuboid> {
+    decomposer.decompose(voxel_grid, scale)
+}
+
+pub fn extract_slopes_from(voxel_grid: &VoxelGrid, scale: u16) -> Vec<Slope> {
+    find_stair_steps(voxel_grid, scale)
+}
+
+/// Returns the cubes making up whatever a Decomposer found, where
+/// each cuboid is split up into the permitted cube sizes.
+pub fn cuboids_to_largest_cubes(
+    cuboids: Vec<Cuboid>,
+    cube_sizes: &CubeSizes,
+    scale: u16,
+) -> Vec<LargestCube> {
+    cuboids
+        .iter()
+        .flat_map(|cuboid| cuboid.to_cubes(cube_sizes, scale))
+        .collect()
+}
+
+/// Returns the cuboids along with the boxes the cubes and the slopes
+/// take up, for save writers whose bricks can be any size.
+pub fn cuboids_with_shapes(
+    mut cuboids: Vec<Cuboid>,
+    largest_cubes: Vec<LargestCube>,
+    slopes: &[Slope],
+) -> Vec<Cuboid> {
+    cuboids.extend(largest_cubes.into_iter().map(Cuboid::from));
+    cuboids.extend(slopes.iter().map(|slope| Cuboid {
+        dimensions: slope_to_brick(slope).dimensions(),
+        indexes: slope.indexes,
+    }));
+    cuboids
 }
 
 pub fn extract_bricks_from(largest_cubes: Vec<LargestCube>) -> Vec<Brick> {
+    extract_bricks_with_slopes_from(largest_cubes, Vec::new())
+}
+
+pub fn extract_bricks_with_slopes_from(
+    largest_cubes: Vec<LargestCube>,
+    slopes: Vec<Slope>,
+) -> Vec<Brick> {
+    extract_bricks_placed_from(
+        largest_cubes,
+        slopes,
+        Origin::default(),
+        Elevation::default(),
+    )
+}
+
+fn slope_to_brick(slope: &Slope) -> Brick {
+    let shape = if slope.run == 2 {
+        BrickShape::LongRamp
+    } else {
+        BrickShape::Ramp
+    };
+
+    Brick::new_oriented(slope.indexes, slope.side_length, shape, slope.rises_toward)
+}
+
+/// Returns the bricks of the cubes and the slopes in the default
+/// BrickOrder, so that the same model always comes out the same way.
+pub fn extract_bricks_placed_from(
+    largest_cubes: Vec<LargestCube>,
+    slopes: Vec<Slope>,
+    origin: Origin,
+    elevation: Elevation,
+) -> Vec<Brick> {
+    let bricks = extract_bricks_with_blocks_placed_from(
+        largest_cubes,
+        slopes,
+        Vec::new(),
+        origin,
+        elevation,
+    );
+
+    order_bricks_by(bricks, BrickOrder::default())
+}
+
+/// Returns the bricks of the cubes, the slopes and the interactive
+/// blocks, where the bricks of each interactive block remember which
+/// block they were converted from so that event rules can find them.
+///
+/// The bricks of each door share a name, `_door1` for the first door
+/// and so on, so that opening either half opens the whole door.
+///
+/// Bricks are left in the order they were found in, to be put in
+/// whichever BrickOrder is wanted afterward.
+pub fn extract_bricks_with_blocks_placed_from(
+    largest_cubes: Vec<LargestCube>,
+    slopes: Vec<Slope>,
+    interactive_blocks: Vec<InteractiveBlock>,
+    origin: Origin,
+    elevation: Elevation,
+) -> Vec<Brick> {
     let mut brick_builder = BrickBuilder::new();
+    brick_builder.with_origin(origin);
+    brick_builder.with_elevation(elevation);
 
     for largest_cube in largest_cubes {
         let brick = Brick::new(largest_cube.indexes, largest_cube.side_length);
         brick_builder.with_brick(brick);
     }
 
+    for slope in slopes {
+        brick_builder.with_brick(slope_to_brick(&slope));
+    }
+
+    let mut door_count = 0;
+    for interactive_block in interactive_blocks {
+        let name = if DOOR_IDS.contains(&interactive_block.block_id) {
+            door_count += 1;
+            Some(format!("_door{}", door_count))
+        } else {
+            None
+        };
+
+        for cube in interactive_block.cubes {
+            let mut brick = Brick::new(cube.indexes, cube.side_length);
+            brick.source_block = Some(interactive_block.block_id);
+            brick.name = name.clone();
+            brick_builder.with_brick(brick);
+        }
+    }
+
     brick_builder.build()
 }
 
-pub fn write_save_file(bricks: &Vec<Brick>, file_name: String) {
-    let mut save_file =
+/// Converts the schematic straight into a save file without holding
+/// the whole model in memory, returning how many bricks were written,
+/// or why the schematic could not be read or the save file written.
+#[allow(clippy::too_many_arguments)]
+pub fn stream_save_file_from(
+    model_path: &Path,
+    file_name: String,
+    scale: u16,
+    cube_sizes: &CubeSizes,
+    threads: usize,
+    origin: Origin,
+    elevation: Elevation,
+    bls_writer: &BlsWriter,
+) -> io::Result<usize> {
+    streaming::stream_schematic_to_save_file(
+        model_path,
+        Path::new(&file_name),
+        scale,
+        cube_sizes,
+        threads,
+        origin,
+        elevation,
+        bls_writer,
+    )
+}
+
+/// Returns the bricks in the given order, which is what they are
+/// written to the save file in.
+pub fn order_bricks_by(mut bricks: Vec<Brick>, brick_order: BrickOrder) -> Vec<Brick> {
+    sort_bricks(&mut bricks, brick_order);
+    bricks
+}
+
+/// Returns the bricks in build order, along with how many bricks at
+/// the end have no path down to the ground.
+pub fn order_bricks_for_building(bricks: Vec<Brick>) -> (Vec<Brick>, usize) {
+    sort_for_building(bricks)
+}
+
+/// Writes the bricks into several save files, each named after
+/// file_name with what sets it apart from the others added before
+/// the extension, and returns the names of the files written.
+pub fn write_split_save_files(
+    bricks: Vec<Brick>,
+    save_split: SaveSplit,
+    save_writer: &dyn SaveWriter,
+    file_name: &str,
+) -> Vec<String> {
+    let extension = format!(".{}", save_writer.extension());
+    let file_stem = file_name.strip_suffix(&extension).unwrap_or(file_name);
+
+    split_bricks(bricks, save_split)
+        .into_iter()
+        .map(|save_part| {
+            let part_file_name = format!("{}_{}{}", file_stem, save_part.label, extension);
+            write_save_file_with(&save_part.bricks, save_writer, part_file_name.clone());
+            part_file_name
+        })
+        .collect()
+}
+
+/// Returns the base save file with the bricks added offset Blockland
+/// units away, along with how many of them overlap bricks of the
+/// base save file.
+pub fn merge_bricks_into(
+    base_save_file: &SaveFile,
+    bricks: &[Brick],
+    offset: (f32, f32, f32),
+    bls_writer: &BlsWriter,
+) -> (SaveFile, usize) {
+    merge_into(
+        base_save_file,
+        bricks,
+        offset,
+        bls_writer,
+        &BrickCatalog::new(),
+    )
+}
+
+pub fn write_merged_save_file(save_file: &SaveFile, file_name: String) {
+    let mut merged_file =
         File::create(file_name).expect("schematic2bls: Could not create save file.");
-    let save_file_content = to_save_file_output(bricks);
-    save_file
-        .write_all(save_file_content.as_bytes())
+    merged_file
+        .write_all(&encode_save_file(&save_file.to_string()))
         .expect("schematic2bls: Could not write save data to new save file");
 }
+
+pub fn write_save_file(bricks: &[Brick], file_name: String) {
+    write_save_file_with(bricks, &BlsWriter::default(), file_name);
+}
+
+/// Writes the bricks into a new file in whichever format the save
+/// writer is for.
+pub fn write_save_file_with(bricks: &[Brick], save_writer: &dyn SaveWriter, file_name: String) {
+    let save_file = File::create(file_name).expect("schematic2bls: Could not create save file.");
+    let mut save_file = BufWriter::new(save_file);
+    save_writer
+        .write_bricks(bricks, &mut save_file)
+        .and_then(|_| save_file.flush())
+        .expect("schematic2bls: Could not write save data to new save file");
+}
+
+/// Writes the cuboids whole into a new file, for save writers whose
+/// bricks can be any size.
+pub fn write_cuboid_save_file_with(
+    cuboids: &[Cuboid],
+    save_writer: &dyn SaveWriter,
+    origin: Origin,
+    elevation: Elevation,
+    file_name: String,
+) {
+    let save_file = File::create(file_name).expect("schematic2bls: Could not create save file.");
+    let mut save_file = BufWriter::new(save_file);
+    save_writer
+        .write_cuboids(cuboids, origin, elevation, &mut save_file)
+        .and_then(|_| save_file.flush())
+        .expect("schematic2bls: Could not write save data to new save file");
+}
+
+pub fn load_save_file(save_file_path: &Path) -> SaveFile {
+    let contents = fs::read(save_file_path).expect("schematic2bls: Could not read save file.");
+    parse_save_file(&decode_save_file(&contents))
+        .unwrap_or_else(|parse_error| panic!("schematic2bls: {}", parse_error))
+}
+
+/// Returns the bricks of the save file as voxels scale studs wide,
+/// along with how many bricks had to be left out because their size
+/// is unknown.
+pub fn parse_grid_from_save_file(
+    save_file: &SaveFile,
+    brick_catalog: &BrickCatalog,
+    scale: u16,
+) -> (VoxelGrid, usize) {
+    save_file_to_3dgrid(save_file, brick_catalog, scale)
+}
+
+/// Writes the grid as a Sponge schematic if the file name ends in
+/// .schem, or as an MCEdit schematic otherwise.
+pub fn write_schematic(voxel_grid: &VoxelGrid, file_name: String) {
+    let schematic_root = if file_name.ends_with(".schem") {
+        grid_to_sponge_schematic(voxel_grid)
+    } else {
+        grid_to_schematic(voxel_grid)
+    };
+
+    let mut schematic_file =
+        File::create(file_name).expect("schematic2bls: Could not create schematic file.");
+    write_gzip_compound_tag(&mut schematic_file, &schematic_root)
+        .expect("schematic2bls: Could not write schematic data to new schematic file.");
+}
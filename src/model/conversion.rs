@@ -1,7 +1,41 @@
 use crate::largest_cube::mapping::{idx_3d_from, GridSizes};
+use crate::largest_cube::LargestCube;
+use crate::model::volume::VoxelGrid;
+use crate::slopes::{Facing, Slope};
 use nbt::CompoundTag;
+use std::collections::{HashMap, HashSet};
 
-pub fn schematic_to_3dgrid(schematic_root: CompoundTag) -> Vec<Vec<Vec<bool>>> {
+/// A model whose blocks are filled in less than one in this many
+/// places is converted into a sparse grid.
+pub(crate) const SPARSE_FILL_RATIO: usize = 4;
+
+/// Returns how the blocks of a schematic are laid out, which is along
+/// X of the schematic, whose size is its Width, then along Z, whose
+/// size is its Length, then up a layer.
+///
+/// These are also the sizes of the voxel grid the blocks end up in.
+pub(crate) fn block_sizes_of(width: usize, length: usize, height: usize) -> GridSizes {
+    GridSizes {
+        x_len: width,
+        y_len: length,
+        z_len: height,
+    }
+}
+
+/// Returns where a block of a schematic ends up in the voxel grid.
+///
+/// Minecraft's up (Y) becomes up (Z) in Blockland. Minecraft's south
+/// (positive Z) becomes negative Y, so that models are not mirrored.
+pub(crate) fn block_to_grid_idx(
+    blocks_idx_1d: usize,
+    block_sizes: &GridSizes,
+) -> (usize, usize, usize) {
+    let (x, z, y) = idx_3d_from(blocks_idx_1d, block_sizes);
+
+    (x, block_sizes.y_len - 1 - z, y)
+}
+
+pub fn schematic_to_3dgrid(schematic_root: CompoundTag) -> VoxelGrid {
     let length = schematic_root
         .get_i16("Length")
         .expect("Could not find Length field.") as usize;
@@ -12,21 +46,589 @@ pub fn schematic_to_3dgrid(schematic_root: CompoundTag) -> Vec<Vec<Vec<bool>>> {
         .get_i16("Height")
         .expect("Could not get Height field") as usize;
 
-    let mut grid = vec![vec![vec![false; width]; height]; length];
-
     let blocks = schematic_root
         .get_i8_vec("Blocks")
         .expect("Could not get Blocks field in schematic.");
-    let grid_size = GridSizes {
-        x_len: length,
-        y_len: width,
-        z_len: height,
+
+    // Models that are mostly empty space, such as world exports,
+    // only keep the chunks that have something in them.
+    let filled_count = blocks
+        .iter()
+        .filter(|block_entry| **block_entry == 1)
+        .count();
+    let mut grid = if filled_count * SPARSE_FILL_RATIO < blocks.len() {
+        VoxelGrid::new_sparse(width, length, height)
+    } else {
+        VoxelGrid::new(width, length, height)
     };
+    let block_sizes = block_sizes_of(width, length, height);
 
     for (blocks_idx_1d, block_entry) in blocks.iter().enumerate() {
-        let (i, j, k) = idx_3d_from(blocks_idx_1d, &grid_size);
-        grid[j][k][i] = *block_entry == 1;
+        let (i, j, k) = block_to_grid_idx(blocks_idx_1d, &block_sizes);
+        grid.set(i, j, k, *block_entry == 1);
     }
 
     grid
 }
+
+/// Returns one byte per block of the grid, laid out the way
+/// schematic_to_3dgrid reads them, where filled voxels become
+/// filled_id and empty ones become 0.
+fn grid_to_blocks(voxel_grid: &VoxelGrid, filled_id: i8) -> Vec<i8> {
+    let block_sizes = voxel_grid.size();
+
+    let mut blocks = vec![0; block_sizes.x_len * block_sizes.y_len * block_sizes.z_len];
+    for (blocks_idx_1d, block_entry) in blocks.iter_mut().enumerate() {
+        let (i, j, k) = block_to_grid_idx(blocks_idx_1d, block_sizes);
+        if voxel_grid.get(i, j, k) {
+            *block_entry = filled_id;
+        }
+    }
+
+    blocks
+}
+
+/// Returns the Width, Height and Length of a schematic holding the
+/// grid.
+fn schematic_sizes_of(voxel_grid: &VoxelGrid) -> (i16, i16, i16) {
+    let sizes = voxel_grid.size();
+    let schematic_size =
+        |len: usize| i16::try_from(len).expect("Model is too large to fit in a schematic.");
+
+    (
+        schematic_size(sizes.x_len),
+        schematic_size(sizes.z_len),
+        schematic_size(sizes.y_len),
+    )
+}
+
+/// Returns the grid as an MCEdit schematic of stone blocks, which
+/// schematic_to_3dgrid turns back into the same grid.
+pub fn grid_to_schematic(voxel_grid: &VoxelGrid) -> CompoundTag {
+    let (width, height, length) = schematic_sizes_of(voxel_grid);
+    let blocks = grid_to_blocks(voxel_grid, STONE_ID);
+
+    let mut schematic_root = CompoundTag::named("Schematic");
+    schematic_root.insert_i16("Width", width);
+    schematic_root.insert_i16("Height", height);
+    schematic_root.insert_i16("Length", length);
+    schematic_root.insert_str("Materials", "Alpha");
+    schematic_root.insert_i8_vec("Data", vec![0; blocks.len()]);
+    schematic_root.insert_i8_vec("Blocks", blocks);
+    schematic_root.insert_compound_tag_vec("Entities", Vec::new());
+    schematic_root.insert_compound_tag_vec("TileEntities", Vec::new());
+
+    schematic_root
+}
+
+/// The Minecraft version Sponge schematics are marked as coming
+/// from, which is 1.16.5.
+const SPONGE_DATA_VERSION: i32 = 2586;
+
+/// Returns the grid as a version 2 Sponge schematic (.schem) of
+/// stone blocks, as read by WorldEdit from Minecraft 1.13 onward.
+pub fn grid_to_sponge_schematic(voxel_grid: &VoxelGrid) -> CompoundTag {
+    let (width, height, length) = schematic_sizes_of(voxel_grid);
+
+    let mut palette = CompoundTag::new();
+    palette.insert_i32("minecraft:air", 0);
+    palette.insert_i32("minecraft:stone", 1);
+
+    let mut schematic_root = CompoundTag::named("Schematic");
+    schematic_root.insert_i32("Version", 2);
+    schematic_root.insert_i32("DataVersion", SPONGE_DATA_VERSION);
+    schematic_root.insert_i16("Width", width);
+    schematic_root.insert_i16("Height", height);
+    schematic_root.insert_i16("Length", length);
+    schematic_root.insert_i32("PaletteMax", 2);
+    schematic_root.insert_compound_tag("Palette", palette);
+    // Palette indexes below 128 take up a single byte as a varint.
+    schematic_root.insert_i8_vec("BlockData", grid_to_blocks(voxel_grid, 1));
+
+    schematic_root
+}
+
+const STONE_ID: i8 = 1;
+const STAIRS_IDS: [u8; 14] = [
+    53, 67, 108, 109, 114, 128, 134, 135, 136, 156, 163, 164, 180, 203,
+];
+const SLAB_IDS: [u8; 4] = [44, 126, 182, 205];
+/// Doors of every kind of wood and iron, followed by trapdoors.
+pub const DOOR_IDS: [u8; 9] = [64, 71, 193, 194, 195, 196, 197, 96, 167];
+/// Stone and wooden buttons, followed by levers.
+pub const BUTTON_IDS: [u8; 3] = [77, 143, 69];
+const TRAPDOOR_IDS: [u8; 2] = [96, 167];
+
+/// An axis of the voxel grid (0 for X, 1 for Y, 2 for Z) along with
+/// whether it points toward the positive or negative end.
+type GridDirection = (usize, bool);
+
+/// A door, button or other block players interact with, along with
+/// the cubes it is built out of, where both halves of a door make up
+/// one block.
+#[derive(PartialEq, Debug, Clone)]
+pub struct InteractiveBlock {
+    pub block_id: u8,
+    pub cubes: Vec<LargestCube>,
+}
+
+/// Blocks in a schematic that are not full cubes, already broken
+/// down into the bricks that best represent them.
+#[derive(Default)]
+pub struct ShapedBlocks {
+    pub cubes: Vec<LargestCube>,
+    pub slopes: Vec<Slope>,
+    pub interactive_blocks: Vec<InteractiveBlock>,
+    voxels: HashSet<(usize, usize, usize)>,
+}
+
+impl ShapedBlocks {
+    /// Returns whether a slope would take up the same voxel as one
+    /// of these blocks, looking at both voxels of a long ramp.
+    pub fn occupies(&self, slope: &Slope) -> bool {
+        let side_length = slope.side_length as usize;
+        let end = (
+            slope.indexes.0 / side_length,
+            slope.indexes.1 / side_length,
+            slope.indexes.2 / side_length,
+        );
+        let (x_run, y_run) = match slope.rises_toward {
+            Facing::PositiveX | Facing::NegativeX => (slope.run, 1),
+            Facing::PositiveY | Facing::NegativeY => (1, slope.run),
+        };
+
+        (end.0 - x_run..end.0)
+            .any(|x| (end.1 - y_run..end.1).any(|y| self.voxels.contains(&(x, y, end.2 - 1))))
+    }
+
+    /// Places cubes of half the scale in each octant of the voxel
+    /// at grid_idx for which is_filled is true.
+    fn fill_octants(
+        &mut self,
+        grid_idx: (usize, usize, usize),
+        scale: u16,
+        is_filled: impl Fn([usize; 3]) -> bool,
+    ) {
+        self.cubes.extend(octant_cubes(grid_idx, scale, is_filled));
+    }
+}
+
+/// Returns cubes of half the scale in each octant of the voxel at
+/// grid_idx for which is_filled is true.
+///
+/// When the scale cannot be halved, the whole voxel is filled in.
+fn octant_cubes(
+    grid_idx: (usize, usize, usize),
+    scale: u16,
+    is_filled: impl Fn([usize; 3]) -> bool,
+) -> Vec<LargestCube> {
+    let scale = scale as usize;
+    if scale < 2 || !scale.is_multiple_of(2) {
+        return vec![LargestCube {
+            side_length: scale as u16,
+            indexes: (
+                (grid_idx.0 + 1) * scale,
+                (grid_idx.1 + 1) * scale,
+                (grid_idx.2 + 1) * scale,
+            ),
+        }];
+    }
+
+    let half = scale / 2;
+    let mut cubes = Vec::new();
+    for a in 0..2 {
+        for b in 0..2 {
+            for c in 0..2 {
+                if !is_filled([a, b, c]) {
+                    continue;
+                }
+
+                cubes.push(LargestCube {
+                    side_length: half as u16,
+                    indexes: (
+                        grid_idx.0 * scale + (a + 1) * half,
+                        grid_idx.1 * scale + (b + 1) * half,
+                        grid_idx.2 * scale + (c + 1) * half,
+                    ),
+                });
+            }
+        }
+    }
+
+    cubes
+}
+
+/// The direction of the voxel grid that bricks and ramps are built
+/// upward along.
+const GRID_UP: GridDirection = (2, true);
+
+/// Returns where the Minecraft directions of a schematic end up in
+/// the voxel grid, the same way block_to_grid_idx places blocks.
+fn minecraft_to_grid_direction(minecraft_axis: usize, positive: bool) -> GridDirection {
+    match minecraft_axis {
+        0 => (0, positive),
+        1 => (2, positive),
+        _ => (1, !positive),
+    }
+}
+
+/// Returns the direction a stairs block rises toward from its data
+/// value, which is east, west, south or north in that order.
+fn stairs_facing(data: u8) -> GridDirection {
+    match data & 0b11 {
+        0 => minecraft_to_grid_direction(0, true),
+        1 => minecraft_to_grid_direction(0, false),
+        2 => minecraft_to_grid_direction(2, true),
+        _ => minecraft_to_grid_direction(2, false),
+    }
+}
+
+/// Returns the direction of the block a button or lever is attached
+/// to from its data value, which is facing east, west, south or north
+/// for one on a wall, then up for one on the floor. Levers can also
+/// be on the floor facing another way, and both can be on the ceiling.
+fn attached_toward(data: u8) -> GridDirection {
+    match data & 0b111 {
+        1 => minecraft_to_grid_direction(0, false),
+        2 => minecraft_to_grid_direction(0, true),
+        3 => minecraft_to_grid_direction(2, false),
+        4 => minecraft_to_grid_direction(2, true),
+        5 | 6 => (GRID_UP.0, !GRID_UP.1),
+        _ => GRID_UP,
+    }
+}
+
+fn grid_direction_to_facing(direction: GridDirection) -> Option<Facing> {
+    match direction {
+        (0, true) => Some(Facing::PositiveX),
+        (0, false) => Some(Facing::NegativeX),
+        (1, true) => Some(Facing::PositiveY),
+        (1, false) => Some(Facing::NegativeY),
+        _ => None,
+    }
+}
+
+/// Returns whether an octant lies in the half of a voxel toward
+/// some direction.
+fn octant_toward(octant: [usize; 3], (axis, positive): GridDirection) -> bool {
+    octant[axis] == positive as usize
+}
+
+/// Returns the stairs, slabs, doors and buttons of a schematic as
+/// bricks.
+///
+/// Stairs that stand upright in the grid become ramps. Otherwise,
+/// stairs and slabs are built out of cubes of half the scale. Each
+/// half of a door becomes a cube as large as a voxel, while buttons
+/// and levers become a cube of half the scale against the block they
+/// are attached to.
+pub fn schematic_to_shaped_blocks(schematic_root: &CompoundTag, scale: u16) -> ShapedBlocks {
+    let length = schematic_root
+        .get_i16("Length")
+        .expect("Could not find Length field.") as usize;
+    let width = schematic_root
+        .get_i16("Width")
+        .expect("Could not find Width field.") as usize;
+    let height = schematic_root
+        .get_i16("Height")
+        .expect("Could not get Height field") as usize;
+
+    let blocks = schematic_root
+        .get_i8_vec("Blocks")
+        .expect("Could not get Blocks field in schematic.");
+    let block_data = schematic_root.get_i8_vec("Data").ok();
+    let block_sizes = block_sizes_of(width, length, height);
+
+    let mut shaped_blocks = ShapedBlocks::default();
+    // Which interactive block the bottom half of a door at each voxel
+    // went into, for its top half to join.
+    let mut bottom_door_halves = HashMap::new();
+    for (blocks_idx_1d, block_entry) in blocks.iter().enumerate() {
+        let block_id = *block_entry as u8;
+        let data = block_data
+            .and_then(|block_data| block_data.get(blocks_idx_1d))
+            .map_or(0, |data| *data as u8);
+
+        let grid_idx = block_to_grid_idx(blocks_idx_1d, &block_sizes);
+        let is_interactive = DOOR_IDS.contains(&block_id) || BUTTON_IDS.contains(&block_id);
+        if STAIRS_IDS.contains(&block_id) || SLAB_IDS.contains(&block_id) || is_interactive {
+            shaped_blocks.voxels.insert(grid_idx);
+        }
+
+        if BUTTON_IDS.contains(&block_id) {
+            let attached = attached_toward(data);
+            let mut attached_octant = [0; 3];
+            attached_octant[attached.0] = attached.1 as usize;
+            shaped_blocks.interactive_blocks.push(InteractiveBlock {
+                block_id,
+                cubes: octant_cubes(grid_idx, scale, |octant| octant == attached_octant),
+            });
+            continue;
+        }
+
+        if DOOR_IDS.contains(&block_id) {
+            let door_half = LargestCube {
+                side_length: scale,
+                indexes: (
+                    (grid_idx.0 + 1) * scale as usize,
+                    (grid_idx.1 + 1) * scale as usize,
+                    (grid_idx.2 + 1) * scale as usize,
+                ),
+            };
+            let is_tall_door = !TRAPDOOR_IDS.contains(&block_id);
+            let is_top_half = is_tall_door && data & 0b1000 != 0;
+            let block_below = grid_idx
+                .2
+                .checked_sub(1)
+                .map(|z| (grid_idx.0, grid_idx.1, z));
+            let bottom_half =
+                block_below.and_then(|block_below| bottom_door_halves.get(&block_below));
+
+            match bottom_half {
+                Some(&door_idx) if is_top_half => {
+                    let door: &mut InteractiveBlock =
+                        &mut shaped_blocks.interactive_blocks[door_idx];
+                    door.cubes.push(door_half);
+                }
+                _ => {
+                    if is_tall_door && !is_top_half {
+                        bottom_door_halves.insert(grid_idx, shaped_blocks.interactive_blocks.len());
+                    }
+                    shaped_blocks.interactive_blocks.push(InteractiveBlock {
+                        block_id,
+                        cubes: vec![door_half],
+                    });
+                }
+            }
+            continue;
+        }
+
+        if STAIRS_IDS.contains(&block_id) {
+            let upside_down = data & 0b100 != 0;
+            let up = minecraft_to_grid_direction(1, !upside_down);
+            let facing = stairs_facing(data);
+
+            if up == GRID_UP {
+                if let Some(rises_toward) = grid_direction_to_facing(facing) {
+                    shaped_blocks.slopes.push(Slope {
+                        side_length: scale,
+                        indexes: (
+                            (grid_idx.0 + 1) * scale as usize,
+                            (grid_idx.1 + 1) * scale as usize,
+                            (grid_idx.2 + 1) * scale as usize,
+                        ),
+                        rises_toward,
+                        run: 1,
+                    });
+                    continue;
+                }
+            }
+
+            let down = (up.0, !up.1);
+            shaped_blocks.fill_octants(grid_idx, scale, |octant| {
+                octant_toward(octant, down) || octant_toward(octant, facing)
+            });
+        } else if SLAB_IDS.contains(&block_id) {
+            let top_half = data & 0b1000 != 0;
+            let half = minecraft_to_grid_direction(1, top_half);
+
+            shaped_blocks.fill_octants(grid_idx, scale, |octant| octant_toward(octant, half));
+        }
+    }
+
+    shaped_blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_block_schematic(block_id: i8, data: i8) -> CompoundTag {
+        let mut schematic_root = CompoundTag::new();
+        schematic_root.insert_i16("Length", 1);
+        schematic_root.insert_i16("Width", 1);
+        schematic_root.insert_i16("Height", 1);
+        schematic_root.insert_i8_vec("Blocks", vec![block_id]);
+        schematic_root.insert_i8_vec("Data", vec![data]);
+
+        schematic_root
+    }
+
+    #[test]
+    fn grid_survives_schematic_round_trip() {
+        let mut expected = VoxelGrid::new(3, 2, 5);
+        expected.set(0, 0, 0, true);
+        expected.set(2, 1, 0, true);
+        expected.set(1, 0, 4, true);
+
+        let schematic_root = grid_to_schematic(&expected);
+        let actual = schematic_to_3dgrid(schematic_root.clone());
+
+        assert_eq!(schematic_root.get_i16("Width").ok(), Some(3));
+        assert_eq!(schematic_root.get_i16("Length").ok(), Some(2));
+        assert_eq!(schematic_root.get_i16("Height").ok(), Some(5));
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn minecraft_up_becomes_grid_z() {
+        let mut schematic_root = CompoundTag::new();
+        schematic_root.insert_i16("Length", 2);
+        schematic_root.insert_i16("Width", 1);
+        schematic_root.insert_i16("Height", 2);
+        // The north block of the bottom layer, then the south block of
+        // the top layer.
+        schematic_root.insert_i8_vec("Blocks", vec![1, 0, 0, 1]);
+
+        let actual = schematic_to_3dgrid(schematic_root);
+
+        let mut expected = VoxelGrid::new(1, 2, 2);
+        expected.set(0, 1, 0, true);
+        expected.set(0, 0, 1, true);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn sponge_schematic_uses_palette() {
+        let mut voxel_grid = VoxelGrid::new(2, 1, 1);
+        voxel_grid.set(1, 0, 0, true);
+
+        let schematic_root = grid_to_sponge_schematic(&voxel_grid);
+
+        assert_eq!(
+            schematic_root.get_i8_vec("BlockData").ok(),
+            Some(&vec![0, 1])
+        );
+        assert_eq!(schematic_root.get_i32("PaletteMax").ok(), Some(2));
+    }
+
+    #[test]
+    fn bottom_slab_is_half_cubes() {
+        let schematic_root = single_block_schematic(44, 0);
+
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 4);
+
+        assert_eq!(shaped_blocks.cubes.len(), 4);
+        assert!(shaped_blocks
+            .cubes
+            .iter()
+            .all(|cube| cube.side_length == 2 && cube.indexes.2 == 2));
+    }
+
+    #[test]
+    fn stairs_become_ramp() {
+        // Oak stairs rising toward the north.
+        let schematic_root = single_block_schematic(53, 3);
+
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 4);
+
+        let expected = vec![Slope {
+            side_length: 4,
+            indexes: (4, 4, 4),
+            rises_toward: Facing::PositiveY,
+            run: 1,
+        }];
+        assert_eq!(expected, shaped_blocks.slopes);
+        assert!(shaped_blocks.cubes.is_empty());
+    }
+
+    #[test]
+    fn upside_down_stairs_are_six_half_cubes() {
+        let schematic_root = single_block_schematic(53, 0b100);
+
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 4);
+
+        assert_eq!(shaped_blocks.cubes.len(), 6);
+        assert!(shaped_blocks.slopes.is_empty());
+        // The upside down stairs fill the whole top half.
+        let top_half_count = shaped_blocks
+            .cubes
+            .iter()
+            .filter(|cube| cube.indexes.2 == 4)
+            .count();
+        assert_eq!(top_half_count, 4);
+    }
+
+    #[test]
+    fn button_is_half_scale_cube_against_wall() {
+        // A stone button facing west, on the wall to its east.
+        let schematic_root = single_block_schematic(77, 2);
+
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 4);
+
+        let expected = vec![InteractiveBlock {
+            block_id: 77,
+            cubes: vec![LargestCube {
+                side_length: 2,
+                indexes: (4, 2, 2),
+            }],
+        }];
+        assert_eq!(expected, shaped_blocks.interactive_blocks);
+        assert!(shaped_blocks.cubes.is_empty());
+    }
+
+    #[test]
+    fn door_halves_are_one_interactive_block() {
+        let mut schematic_root = CompoundTag::new();
+        schematic_root.insert_i16("Length", 1);
+        schematic_root.insert_i16("Width", 1);
+        schematic_root.insert_i16("Height", 2);
+        schematic_root.insert_i8_vec("Blocks", vec![64, 64]);
+        schematic_root.insert_i8_vec("Data", vec![0, 8]);
+
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 4);
+
+        let expected = vec![InteractiveBlock {
+            block_id: 64,
+            cubes: vec![
+                LargestCube {
+                    side_length: 4,
+                    indexes: (4, 4, 4),
+                },
+                LargestCube {
+                    side_length: 4,
+                    indexes: (4, 4, 8),
+                },
+            ],
+        }];
+        assert_eq!(expected, shaped_blocks.interactive_blocks);
+    }
+
+    #[test]
+    fn long_ramp_occupies_both_of_its_voxels() {
+        // A slab, then stone, along X.
+        let mut schematic_root = CompoundTag::new();
+        schematic_root.insert_i16("Length", 1);
+        schematic_root.insert_i16("Width", 2);
+        schematic_root.insert_i16("Height", 1);
+        schematic_root.insert_i8_vec("Blocks", vec![44, 1]);
+        schematic_root.insert_i8_vec("Data", vec![0, 0]);
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 4);
+        let long_ramp = |rises_toward| Slope {
+            side_length: 4,
+            indexes: (8, 4, 4),
+            rises_toward,
+            run: 2,
+        };
+
+        assert!(shaped_blocks.occupies(&long_ramp(Facing::PositiveX)));
+        assert!(shaped_blocks.occupies(&long_ramp(Facing::NegativeX)));
+        assert!(!shaped_blocks.occupies(&Slope {
+            run: 1,
+            ..long_ramp(Facing::PositiveX)
+        }));
+    }
+
+    #[test]
+    fn odd_scale_slab_is_full_cube() {
+        let schematic_root = single_block_schematic(44, 8);
+
+        let shaped_blocks = schematic_to_shaped_blocks(&schematic_root, 1);
+
+        let expected = vec![LargestCube {
+            side_length: 1,
+            indexes: (1, 1, 1),
+        }];
+        assert_eq!(expected, shaped_blocks.cubes);
+    }
+}
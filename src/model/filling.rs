@@ -0,0 +1,399 @@
+use std::collections::VecDeque;
+
+use super::volume::{VoxelGrid, CHUNK_SIDE};
+use crate::largest_cube::mapping::{idx_1d_from, GridSizes};
+
+/// Something the flood fill of the exterior has reached, which is
+/// either a single voxel, or a whole chunk with nothing filled in it.
+enum Reached {
+    Voxel((usize, usize, usize)),
+    Chunk((usize, usize, usize)),
+}
+
+/// Keeps track of which parts of a grid can be reached from the
+/// outside of it.
+///
+/// Chunks with nothing filled in them are flooded all at once, so
+/// that large empty spaces (such as the air around world exports)
+/// cost one step per chunk instead of one per voxel.
+struct Exterior {
+    grid: VoxelGrid,
+    chunk_sizes: GridSizes,
+    empty_chunks: Vec<bool>,
+    reached_chunks: Vec<bool>,
+    reached_voxels: VoxelGrid,
+    to_visit: VecDeque<Reached>,
+}
+
+/// Returns where a chunk starts and ends, where the end is left out
+/// and never goes past the edge of the grid.
+fn chunk_bounds(
+    (chunk_x, chunk_y, chunk_z): (usize, usize, usize),
+    sizes: &GridSizes,
+) -> ((usize, usize, usize), (usize, usize, usize)) {
+    let start = (
+        chunk_x * CHUNK_SIDE,
+        chunk_y * CHUNK_SIDE,
+        chunk_z * CHUNK_SIDE,
+    );
+    let end = (
+        (start.0 + CHUNK_SIDE).min(sizes.x_len),
+        (start.1 + CHUNK_SIDE).min(sizes.y_len),
+        (start.2 + CHUNK_SIDE).min(sizes.z_len),
+    );
+
+    (start, end)
+}
+
+/// Returns every voxel from start up to, but not including, end.
+fn voxels_within(
+    start: (usize, usize, usize),
+    end: (usize, usize, usize),
+) -> impl Iterator<Item = (usize, usize, usize)> {
+    (start.2..end.2).flat_map(move |z| {
+        (start.1..end.1).flat_map(move |y| (start.0..end.0).map(move |x| (x, y, z)))
+    })
+}
+
+impl Exterior {
+    fn new(grid: VoxelGrid) -> Exterior {
+        let sizes = grid.size();
+        let chunk_sizes = GridSizes {
+            x_len: sizes.x_len.div_ceil(CHUNK_SIDE),
+            y_len: sizes.y_len.div_ceil(CHUNK_SIDE),
+            z_len: sizes.z_len.div_ceil(CHUNK_SIDE),
+        };
+        let chunk_side = GridSizes {
+            x_len: CHUNK_SIDE,
+            y_len: CHUNK_SIDE,
+            z_len: CHUNK_SIDE,
+        };
+
+        let empty_chunks = voxels_within(
+            (0, 0, 0),
+            (chunk_sizes.x_len, chunk_sizes.y_len, chunk_sizes.z_len),
+        )
+        .map(|chunk| {
+            let (start, _) = chunk_bounds(chunk, sizes);
+            grid.is_empty_within(start, &chunk_side)
+        })
+        .collect::<Vec<bool>>();
+
+        Exterior {
+            reached_chunks: vec![false; empty_chunks.len()],
+            empty_chunks,
+            chunk_sizes,
+            reached_voxels: grid.empty_like(),
+            to_visit: VecDeque::new(),
+            grid,
+        }
+    }
+
+    fn chunk_idx(&self, (chunk_x, chunk_y, chunk_z): (usize, usize, usize)) -> usize {
+        idx_1d_from(chunk_x, chunk_y, chunk_z, &self.chunk_sizes)
+    }
+
+    fn chunk_of((x, y, z): (usize, usize, usize)) -> (usize, usize, usize) {
+        (x / CHUNK_SIDE, y / CHUNK_SIDE, z / CHUNK_SIDE)
+    }
+
+    fn reach_chunk(&mut self, chunk: (usize, usize, usize)) {
+        let chunk_idx = self.chunk_idx(chunk);
+        if !self.reached_chunks[chunk_idx] {
+            self.reached_chunks[chunk_idx] = true;
+            self.to_visit.push_back(Reached::Chunk(chunk));
+        }
+    }
+
+    fn reach_voxel(&mut self, (x, y, z): (usize, usize, usize)) {
+        if self.grid.get(x, y, z) {
+            return;
+        }
+
+        let chunk = Exterior::chunk_of((x, y, z));
+        if self.empty_chunks[self.chunk_idx(chunk)] {
+            self.reach_chunk(chunk);
+        } else if !self.reached_voxels.get(x, y, z) {
+            self.reached_voxels.set(x, y, z, true);
+            self.to_visit.push_back(Reached::Voxel((x, y, z)));
+        }
+    }
+
+    /// Starts the flood from every empty voxel on the boundary of the
+    /// grid.
+    fn reach_boundary(&mut self) {
+        let sizes = self.grid.size().clone();
+        let last_chunk = (
+            self.chunk_sizes.x_len.saturating_sub(1),
+            self.chunk_sizes.y_len.saturating_sub(1),
+            self.chunk_sizes.z_len.saturating_sub(1),
+        );
+        let chunk_end = (
+            self.chunk_sizes.x_len,
+            self.chunk_sizes.y_len,
+            self.chunk_sizes.z_len,
+        );
+
+        for chunk in voxels_within((0, 0, 0), chunk_end) {
+            let on_boundary = chunk.0 == 0
+                || chunk.1 == 0
+                || chunk.2 == 0
+                || chunk.0 == last_chunk.0
+                || chunk.1 == last_chunk.1
+                || chunk.2 == last_chunk.2;
+            if !on_boundary {
+                continue;
+            }
+
+            if self.empty_chunks[self.chunk_idx(chunk)] {
+                self.reach_chunk(chunk);
+                continue;
+            }
+
+            let (start, end) = chunk_bounds(chunk, &sizes);
+            for (x, y, z) in voxels_within(start, end) {
+                let on_boundary = x == 0
+                    || y == 0
+                    || z == 0
+                    || x + 1 == sizes.x_len
+                    || y + 1 == sizes.y_len
+                    || z + 1 == sizes.z_len;
+                if on_boundary {
+                    self.reach_voxel((x, y, z));
+                }
+            }
+        }
+    }
+
+    /// Floods out from a chunk with nothing in it into the chunks
+    /// next to it, looking only along the X, Y and Z axes.
+    fn reach_from_chunk(&mut self, chunk: (usize, usize, usize)) {
+        let sizes = self.grid.size().clone();
+        let (start, end) = chunk_bounds(chunk, &sizes);
+
+        for axis in 0..3 {
+            for towards_end in [false, true] {
+                let chunk_coordinates = [chunk.0, chunk.1, chunk.2];
+                let chunk_len = [
+                    self.chunk_sizes.x_len,
+                    self.chunk_sizes.y_len,
+                    self.chunk_sizes.z_len,
+                ][axis];
+                let mut neighbor = chunk_coordinates;
+                if towards_end && chunk_coordinates[axis] + 1 < chunk_len {
+                    neighbor[axis] += 1;
+                } else if !towards_end && chunk_coordinates[axis] > 0 {
+                    neighbor[axis] -= 1;
+                } else {
+                    continue;
+                }
+
+                let neighbor = (neighbor[0], neighbor[1], neighbor[2]);
+                if self.empty_chunks[self.chunk_idx(neighbor)] {
+                    self.reach_chunk(neighbor);
+                    continue;
+                }
+
+                // Only the face of the neighbor touching this chunk is
+                // reached.
+                let mut face_start = [start.0, start.1, start.2];
+                let mut face_end = [end.0, end.1, end.2];
+                let face = if towards_end {
+                    face_end[axis]
+                } else {
+                    face_start[axis] - 1
+                };
+                face_start[axis] = face;
+                face_end[axis] = face + 1;
+
+                let face_start = (face_start[0], face_start[1], face_start[2]);
+                let face_end = (face_end[0], face_end[1], face_end[2]);
+                for voxel in voxels_within(face_start, face_end) {
+                    self.reach_voxel(voxel);
+                }
+            }
+        }
+    }
+
+    fn reach_from_voxel(&mut self, (x, y, z): (usize, usize, usize)) {
+        let sizes = self.grid.size();
+        let mut neighbors = Vec::with_capacity(6);
+
+        if x > 0 {
+            neighbors.push((x - 1, y, z));
+        }
+        if x + 1 < sizes.x_len {
+            neighbors.push((x + 1, y, z));
+        }
+        if y > 0 {
+            neighbors.push((x, y - 1, z));
+        }
+        if y + 1 < sizes.y_len {
+            neighbors.push((x, y + 1, z));
+        }
+        if z > 0 {
+            neighbors.push((x, y, z - 1));
+        }
+        if z + 1 < sizes.z_len {
+            neighbors.push((x, y, z + 1));
+        }
+
+        for neighbor in neighbors {
+            self.reach_voxel(neighbor);
+        }
+    }
+
+    fn flood(&mut self) {
+        self.reach_boundary();
+
+        while let Some(reached) = self.to_visit.pop_front() {
+            match reached {
+                Reached::Voxel(voxel) => self.reach_from_voxel(voxel),
+                Reached::Chunk(chunk) => self.reach_from_chunk(chunk),
+            }
+        }
+    }
+
+    /// Returns the grid with every empty voxel the flood did not
+    /// reach filled in, a chunk at a time.
+    fn into_filled(mut self) -> VoxelGrid {
+        let sizes = self.grid.size().clone();
+        let chunk_end = (
+            self.chunk_sizes.x_len,
+            self.chunk_sizes.y_len,
+            self.chunk_sizes.z_len,
+        );
+
+        for chunk in voxels_within((0, 0, 0), chunk_end) {
+            let chunk_idx = self.chunk_idx(chunk);
+            let (start, end) = chunk_bounds(chunk, &sizes);
+
+            if self.empty_chunks[chunk_idx] {
+                if !self.reached_chunks[chunk_idx] {
+                    for (x, y, z) in voxels_within(start, end) {
+                        self.grid.set(x, y, z, true);
+                    }
+                }
+                continue;
+            }
+
+            for (x, y, z) in voxels_within(start, end) {
+                if !self.reached_voxels.get(x, y, z) {
+                    self.grid.set(x, y, z, true);
+                }
+            }
+        }
+
+        self.grid
+    }
+}
+
+/// Returns the same grid where every empty voxel that cannot be
+/// reached from the outside of the grid has been filled in.
+///
+/// Surface-only voxelizations (such as those from binvox) are hollow
+/// on the inside, so the exterior is flood filled from the boundary
+/// of the grid, and anything the flood did not reach is considered
+/// to be an enclosed cavity.
+///
+/// Sparse grids stay sparse, as chunks with nothing in them are
+/// flooded without looking at each of their voxels.
+pub fn fill_enclosed_cavities(grid: VoxelGrid) -> VoxelGrid {
+    let mut exterior = Exterior::new(grid);
+    exterior.flood();
+
+    exterior.into_filled()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hollow_cube(side_length: usize) -> VoxelGrid {
+        let mut grid = vec![vec![vec![false; side_length]; side_length]; side_length];
+
+        for (i, plane) in grid.iter_mut().enumerate() {
+            for (j, column) in plane.iter_mut().enumerate() {
+                for (k, voxel) in column.iter_mut().enumerate() {
+                    let edge = side_length - 1;
+                    *voxel = i == 0 || j == 0 || k == 0 || i == edge || j == edge || k == edge;
+                }
+            }
+        }
+
+        VoxelGrid::from(grid)
+    }
+
+    #[test]
+    fn fills_hollow_cube() {
+        let grid = fill_enclosed_cavities(hollow_cube(4));
+
+        assert_eq!(grid.filled_count(), 4 * 4 * 4);
+    }
+
+    #[test]
+    fn keeps_open_cavity_empty() {
+        let mut grid = hollow_cube(4);
+        // Punching a hole in the shell connects the inside to the outside.
+        grid.set(0, 1, 1, false);
+
+        let grid = fill_enclosed_cavities(grid);
+
+        assert!(!grid.get(0, 1, 1));
+        assert!(!grid.get(1, 1, 1));
+        assert!(!grid.get(2, 2, 2));
+    }
+
+    fn sparse_hollow_cube(side_length: usize) -> VoxelGrid {
+        let mut grid = VoxelGrid::new_sparse(side_length, side_length, side_length);
+        let edge = side_length - 1;
+
+        for (i, j, k) in voxels_within((0, 0, 0), (side_length, side_length, side_length)) {
+            if i == 0 || j == 0 || k == 0 || i == edge || j == edge || k == edge {
+                grid.set(i, j, k, true);
+            }
+        }
+
+        grid
+    }
+
+    #[test]
+    fn fills_empty_chunks_inside_sparse_shell() {
+        // The inside of the shell has whole chunks with nothing in them.
+        let grid = fill_enclosed_cavities(sparse_hollow_cube(40));
+
+        assert!(grid.is_sparse());
+        assert_eq!(grid.filled_count(), 40 * 40 * 40);
+    }
+
+    #[test]
+    fn keeps_open_sparse_shell_empty() {
+        let mut grid = sparse_hollow_cube(40);
+        grid.set(0, 20, 20, false);
+
+        let grid = fill_enclosed_cavities(grid);
+
+        assert!(!grid.get(20, 20, 20));
+        assert!(!grid.get(38, 38, 38));
+    }
+
+    #[test]
+    fn fills_cavity_next_to_empty_chunks() {
+        let mut grid = VoxelGrid::new_sparse(48, 48, 48);
+        for (i, j, k) in voxels_within((20, 20, 20), (23, 23, 23)) {
+            grid.set(i, j, k, (i, j, k) != (21, 21, 21));
+        }
+
+        let grid = fill_enclosed_cavities(grid);
+
+        assert!(grid.get(21, 21, 21));
+        assert_eq!(grid.filled_count(), 3 * 3 * 3);
+    }
+
+    #[test]
+    fn empty_grid_stays_empty() {
+        let grid = fill_enclosed_cavities(VoxelGrid::new(0, 0, 0));
+
+        assert!(grid.is_empty());
+    }
+}
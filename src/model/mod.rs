@@ -1 +1,4 @@
 pub mod conversion;
+pub mod filling;
+pub mod slabs;
+pub mod volume;
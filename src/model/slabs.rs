@@ -0,0 +1,284 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use super::conversion::{block_sizes_of, block_to_grid_idx, SPARSE_FILL_RATIO};
+use super::volume::VoxelGrid;
+use crate::largest_cube::mapping::GridSizes;
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_i16(reader: &mut impl Read) -> io::Result<i16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(i16::from_be_bytes(bytes))
+}
+
+fn read_i32(reader: &mut impl Read) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_be_bytes(bytes))
+}
+
+fn read_name(reader: &mut impl Read) -> io::Result<String> {
+    let name_len = read_i16(reader)? as u16 as usize;
+    let mut name = vec![0; name_len];
+    reader.read_exact(&mut name)?;
+
+    Ok(String::from_utf8_lossy(&name).into_owned())
+}
+
+fn skip_bytes(reader: &mut impl Read, byte_count: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(byte_count), &mut io::sink())?;
+    if skipped != byte_count {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(())
+}
+
+fn array_len(reader: &mut impl Read) -> io::Result<u64> {
+    let len = read_i32(reader)?;
+    if len < 0 {
+        return Err(invalid_data("Negative array length in schematic."));
+    }
+
+    Ok(len as u64)
+}
+
+/// Reads past the payload of a tag without keeping any of it.
+fn skip_payload(reader: &mut impl Read, tag_type: u8) -> io::Result<()> {
+    match tag_type {
+        TAG_BYTE => skip_bytes(reader, 1),
+        TAG_SHORT => skip_bytes(reader, 2),
+        TAG_INT | TAG_FLOAT => skip_bytes(reader, 4),
+        TAG_LONG | TAG_DOUBLE => skip_bytes(reader, 8),
+        TAG_BYTE_ARRAY => {
+            let len = array_len(reader)?;
+            skip_bytes(reader, len)
+        }
+        TAG_INT_ARRAY => {
+            let len = array_len(reader)?;
+            skip_bytes(reader, len * 4)
+        }
+        TAG_LONG_ARRAY => {
+            let len = array_len(reader)?;
+            skip_bytes(reader, len * 8)
+        }
+        TAG_STRING => {
+            let len = read_i16(reader)? as u16 as u64;
+            skip_bytes(reader, len)
+        }
+        TAG_LIST => {
+            let element_type = read_u8(reader)?;
+            let len = array_len(reader)?;
+            for _ in 0..len {
+                skip_payload(reader, element_type)?;
+            }
+            Ok(())
+        }
+        TAG_COMPOUND => loop {
+            let entry_type = read_u8(reader)?;
+            if entry_type == TAG_END {
+                return Ok(());
+            }
+            read_name(reader)?;
+            skip_payload(reader, entry_type)?;
+        },
+        _ => Err(invalid_data("Unknown tag type in schematic.")),
+    }
+}
+
+/// Reads the Blocks of a schematic one slab of layers at a time,
+/// straight from the compressed file, so that the whole model never
+/// has to be in memory at once.
+///
+/// Each layer of a schematic is one step up the Z axis of the
+/// voxel grid, so slabs are stacked along Z.
+pub struct SchematicSlabs<R: Read> {
+    reader: R,
+    length: usize,
+    width: usize,
+    height: usize,
+    layers_read: usize,
+}
+
+impl SchematicSlabs<GzDecoder<BufReader<File>>> {
+    pub fn open(schematic_path: &Path) -> io::Result<Self> {
+        let schematic_file = File::open(schematic_path)?;
+        SchematicSlabs::from_reader(GzDecoder::new(BufReader::new(schematic_file)))
+    }
+}
+
+impl<R: Read> SchematicSlabs<R> {
+    /// Reads up to the start of the Blocks field of an uncompressed
+    /// schematic.
+    ///
+    /// The Width, Height and Length fields have to come before the
+    /// Blocks field, as they do in schematics saved by MCEdit and
+    /// WorldEdit.
+    pub fn from_reader(mut reader: R) -> io::Result<Self> {
+        if read_u8(&mut reader)? != TAG_COMPOUND {
+            return Err(invalid_data(
+                "Schematic does not start with a compound tag.",
+            ));
+        }
+        read_name(&mut reader)?;
+
+        let (mut length, mut width, mut height) = (None, None, None);
+        loop {
+            let tag_type = read_u8(&mut reader)?;
+            if tag_type == TAG_END {
+                return Err(invalid_data("Could not get Blocks field in schematic."));
+            }
+
+            let tag_name = read_name(&mut reader)?;
+            match (tag_type, tag_name.as_str()) {
+                (TAG_SHORT, "Length") => length = Some(read_i16(&mut reader)? as u16 as usize),
+                (TAG_SHORT, "Width") => width = Some(read_i16(&mut reader)? as u16 as usize),
+                (TAG_SHORT, "Height") => height = Some(read_i16(&mut reader)? as u16 as usize),
+                (TAG_BYTE_ARRAY, "Blocks") => break,
+                _ => skip_payload(&mut reader, tag_type)?,
+            }
+        }
+
+        let (Some(length), Some(width), Some(height)) = (length, width, height) else {
+            return Err(invalid_data(
+                "Schematic must have Width, Height and Length before Blocks.",
+            ));
+        };
+        if array_len(&mut reader)? != (length * width * height) as u64 {
+            return Err(invalid_data("Blocks field does not match schematic size."));
+        }
+
+        Ok(SchematicSlabs {
+            reader,
+            length,
+            width,
+            height,
+            layers_read: 0,
+        })
+    }
+
+    /// Returns the sizes of the whole voxel grid, as laid out by
+    /// schematic_to_3dgrid.
+    pub fn size(&self) -> GridSizes {
+        block_sizes_of(self.width, self.length, self.height)
+    }
+
+    /// Returns the next slab of up to layer_count layers, along with
+    /// how far along Z of the whole grid it starts, or None once
+    /// every layer has been read.
+    pub fn next_slab(&mut self, layer_count: usize) -> io::Result<Option<(usize, VoxelGrid)>> {
+        let first_layer = self.layers_read;
+        let layer_count = layer_count.min(self.height - first_layer);
+        if layer_count == 0 {
+            return Ok(None);
+        }
+
+        let mut blocks = vec![0; self.length * self.width * layer_count];
+        self.reader.read_exact(&mut blocks)?;
+        self.layers_read += layer_count;
+
+        let slab_sizes = block_sizes_of(self.width, self.length, layer_count);
+        let filled_count = blocks
+            .iter()
+            .filter(|block_entry| **block_entry == 1)
+            .count();
+        let mut slab = if filled_count * SPARSE_FILL_RATIO < blocks.len() {
+            VoxelGrid::new_sparse(self.width, self.length, layer_count)
+        } else {
+            VoxelGrid::new(self.width, self.length, layer_count)
+        };
+        for (blocks_idx_1d, block_entry) in blocks.iter().enumerate() {
+            let (i, j, k) = block_to_grid_idx(blocks_idx_1d, &slab_sizes);
+            slab.set(i, j, k, *block_entry == 1);
+        }
+
+        Ok(Some((first_layer, slab)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::conversion::schematic_to_3dgrid;
+    use nbt::{encode::write_compound_tag, CompoundTag};
+
+    fn layered_schematic() -> CompoundTag {
+        let mut schematic_root = CompoundTag::new();
+        schematic_root.insert_i16("Height", 5);
+        schematic_root.insert_i16("Length", 3);
+        schematic_root.insert_i16("Width", 4);
+        schematic_root.insert_str("Materials", "Alpha");
+        schematic_root.insert_i8_vec(
+            "Blocks",
+            (0..60)
+                .map(|idx| if idx % 4 == 0 { 1 } else { 0 })
+                .collect(),
+        );
+        schematic_root.insert_i8_vec("Data", vec![0; 60]);
+
+        schematic_root
+    }
+
+    #[test]
+    fn slabs_stack_up_to_whole_grid() {
+        let schematic_root = layered_schematic();
+        let mut schematic_bytes = Vec::new();
+        write_compound_tag(&mut schematic_bytes, &schematic_root).unwrap();
+
+        let expected = schematic_to_3dgrid(schematic_root);
+
+        let mut slabs = SchematicSlabs::from_reader(schematic_bytes.as_slice()).unwrap();
+        let mut actual = VoxelGrid::new(4, 3, 5);
+        while let Some((first_layer, slab)) = slabs.next_slab(2).unwrap() {
+            let slab_sizes = slab.size().clone();
+            for x in 0..slab_sizes.x_len {
+                for y in 0..slab_sizes.y_len {
+                    for z in 0..slab_sizes.z_len {
+                        actual.set(x, y, first_layer + z, slab.get(x, y, z));
+                    }
+                }
+            }
+        }
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn missing_sizes_are_rejected() {
+        let mut schematic_root = CompoundTag::new();
+        schematic_root.insert_i8_vec("Blocks", vec![1]);
+        let mut schematic_bytes = Vec::new();
+        write_compound_tag(&mut schematic_bytes, &schematic_root).unwrap();
+
+        let slabs = SchematicSlabs::from_reader(schematic_bytes.as_slice());
+
+        assert!(slabs.is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::largest_cube::mapping::{idx_1d_from, GridSizes};
+
+const VOXELS_PER_WORD: usize = u64::BITS as usize;
+
+/// How many voxels wide each chunk of a sparse grid is.
+pub const CHUNK_SIDE: usize = 16;
+const CHUNK_WORDS: usize = CHUNK_SIDE * CHUNK_SIDE * CHUNK_SIDE / VOXELS_PER_WORD;
+
+type Chunk = Box<[u64; CHUNK_WORDS]>;
+
+#[derive(Clone, Debug)]
+enum Voxels {
+    /// Every voxel, in the same flat layout as idx_1d_from.
+    Dense(Vec<u64>),
+    /// Chunks of CHUNK_SIDE voxels along each side, also in the flat
+    /// layout of idx_1d_from, where chunks with nothing filled in
+    /// them are left out.
+    Chunked {
+        chunk_sizes: GridSizes,
+        chunks: Vec<Option<Chunk>>,
+    },
+}
+
+/// A grid of filled or empty voxels, packed at one bit per voxel.
+///
+/// Grids are dense by default. Sparse grids only store the chunks
+/// that have something filled in them, which suits large models that
+/// are mostly empty space.
+#[derive(Clone, Debug)]
+pub struct VoxelGrid {
+    sizes: GridSizes,
+    voxels: Voxels,
+}
+
+/// Returns which chunk a voxel falls in, and where the voxel is
+/// within that chunk.
+fn chunk_idx_from(x: usize, y: usize, z: usize, chunk_sizes: &GridSizes) -> (usize, usize) {
+    let chunk_idx = idx_1d_from(x / CHUNK_SIDE, y / CHUNK_SIDE, z / CHUNK_SIDE, chunk_sizes);
+    let voxel_idx = ((z % CHUNK_SIDE) * CHUNK_SIDE + y % CHUNK_SIDE) * CHUNK_SIDE + x % CHUNK_SIDE;
+
+    (chunk_idx, voxel_idx)
+}
+
+fn get_bit(words: &[u64], idx: usize) -> bool {
+    words[idx / VOXELS_PER_WORD] & (1 << (idx % VOXELS_PER_WORD)) != 0
+}
+
+fn set_bit(words: &mut [u64], idx: usize, filled: bool) {
+    let bit = 1 << (idx % VOXELS_PER_WORD);
+
+    if filled {
+        words[idx / VOXELS_PER_WORD] |= bit;
+    } else {
+        words[idx / VOXELS_PER_WORD] &= !bit;
+    }
+}
+
+impl VoxelGrid {
+    pub fn new(x_len: usize, y_len: usize, z_len: usize) -> VoxelGrid {
+        let voxel_count = x_len * y_len * z_len;
+
+        VoxelGrid {
+            sizes: GridSizes {
+                x_len,
+                y_len,
+                z_len,
+            },
+            voxels: Voxels::Dense(vec![0; voxel_count.div_ceil(VOXELS_PER_WORD)]),
+        }
+    }
+
+    /// Returns an empty grid that only takes up memory for the chunks
+    /// that end up with something filled in them.
+    pub fn new_sparse(x_len: usize, y_len: usize, z_len: usize) -> VoxelGrid {
+        let chunk_sizes = GridSizes {
+            x_len: x_len.div_ceil(CHUNK_SIDE),
+            y_len: y_len.div_ceil(CHUNK_SIDE),
+            z_len: z_len.div_ceil(CHUNK_SIDE),
+        };
+        let chunk_count = chunk_sizes.x_len * chunk_sizes.y_len * chunk_sizes.z_len;
+
+        VoxelGrid {
+            sizes: GridSizes {
+                x_len,
+                y_len,
+                z_len,
+            },
+            voxels: Voxels::Chunked {
+                chunk_sizes,
+                chunks: vec![None; chunk_count],
+            },
+        }
+    }
+
+    /// Returns an empty grid of the same sizes, which is sparse if
+    /// this grid is sparse.
+    pub fn empty_like(&self) -> VoxelGrid {
+        let GridSizes {
+            x_len,
+            y_len,
+            z_len,
+        } = self.sizes;
+
+        match self.voxels {
+            Voxels::Dense(_) => VoxelGrid::new(x_len, y_len, z_len),
+            Voxels::Chunked { .. } => VoxelGrid::new_sparse(x_len, y_len, z_len),
+        }
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.voxels, Voxels::Chunked { .. })
+    }
+
+    pub fn size(&self) -> &GridSizes {
+        &self.sizes
+    }
+
+    /// Returns every word of voxels being stored, leaving out any
+    /// chunks that are not.
+    fn words(&self) -> Box<dyn Iterator<Item = &u64> + '_> {
+        match &self.voxels {
+            Voxels::Dense(words) => Box::new(words.iter()),
+            Voxels::Chunked { chunks, .. } => {
+                Box::new(chunks.iter().flatten().flat_map(|chunk| chunk.iter()))
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words().all(|word| *word == 0)
+    }
+
+    pub fn filled_count(&self) -> usize {
+        self.words().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> bool {
+        match &self.voxels {
+            Voxels::Dense(words) => get_bit(words, idx_1d_from(x, y, z, &self.sizes)),
+            Voxels::Chunked {
+                chunk_sizes,
+                chunks,
+            } => {
+                let (chunk_idx, voxel_idx) = chunk_idx_from(x, y, z, chunk_sizes);
+                chunks[chunk_idx]
+                    .as_ref()
+                    .is_some_and(|chunk| get_bit(chunk.as_slice(), voxel_idx))
+            }
+        }
+    }
+
+    /// Returns whether a voxel is filled, where anything outside of
+    /// the grid counts as empty.
+    pub fn get_or_empty(&self, x: isize, y: isize, z: isize) -> bool {
+        let inside = (0..self.sizes.x_len as isize).contains(&x)
+            && (0..self.sizes.y_len as isize).contains(&y)
+            && (0..self.sizes.z_len as isize).contains(&z);
+
+        inside && self.get(x as usize, y as usize, z as usize)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, filled: bool) {
+        match &mut self.voxels {
+            Voxels::Dense(words) => set_bit(words, idx_1d_from(x, y, z, &self.sizes), filled),
+            Voxels::Chunked {
+                chunk_sizes,
+                chunks,
+            } => {
+                let (chunk_idx, voxel_idx) = chunk_idx_from(x, y, z, chunk_sizes);
+                match &mut chunks[chunk_idx] {
+                    Some(chunk) => set_bit(chunk.as_mut_slice(), voxel_idx, filled),
+                    None if filled => {
+                        let mut chunk = Box::new([0; CHUNK_WORDS]);
+                        set_bit(chunk.as_mut_slice(), voxel_idx, true);
+                        chunks[chunk_idx] = Some(chunk);
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Returns whether nothing is filled within the given sizes
+    /// starting at origin, which for sparse grids only has to look at
+    /// the chunks that are being stored.
+    pub fn is_empty_within(&self, origin: (usize, usize, usize), sizes: &GridSizes) -> bool {
+        let end = (
+            (origin.0 + sizes.x_len).min(self.sizes.x_len),
+            (origin.1 + sizes.y_len).min(self.sizes.y_len),
+            (origin.2 + sizes.z_len).min(self.sizes.z_len),
+        );
+
+        let Voxels::Chunked {
+            chunk_sizes,
+            chunks,
+        } = &self.voxels
+        else {
+            return (origin.2..end.2)
+                .all(|z| (origin.1..end.1).all(|y| (origin.0..end.0).all(|x| !self.get(x, y, z))));
+        };
+
+        if end.0 <= origin.0 || end.1 <= origin.1 || end.2 <= origin.2 {
+            return true;
+        }
+
+        for chunk_z in origin.2 / CHUNK_SIDE..=(end.2 - 1) / CHUNK_SIDE {
+            for chunk_y in origin.1 / CHUNK_SIDE..=(end.1 - 1) / CHUNK_SIDE {
+                for chunk_x in origin.0 / CHUNK_SIDE..=(end.0 - 1) / CHUNK_SIDE {
+                    let chunk_idx = idx_1d_from(chunk_x, chunk_y, chunk_z, chunk_sizes);
+                    if chunks[chunk_idx].is_none() {
+                        continue;
+                    }
+
+                    // Only the part of the chunk inside of the region
+                    // counts.
+                    let chunk_origin = (
+                        (chunk_x * CHUNK_SIDE).max(origin.0),
+                        (chunk_y * CHUNK_SIDE).max(origin.1),
+                        (chunk_z * CHUNK_SIDE).max(origin.2),
+                    );
+                    let chunk_end = (
+                        ((chunk_x + 1) * CHUNK_SIDE).min(end.0),
+                        ((chunk_y + 1) * CHUNK_SIDE).min(end.1),
+                        ((chunk_z + 1) * CHUNK_SIDE).min(end.2),
+                    );
+                    for z in chunk_origin.2..chunk_end.2 {
+                        for y in chunk_origin.1..chunk_end.1 {
+                            for x in chunk_origin.0..chunk_end.0 {
+                                if self.get(x, y, z) {
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Returns the part of the grid starting at origin with the given
+    /// sizes, where anything past the edge of the grid is left out.
+    ///
+    /// The cropped grid is always dense.
+    pub fn crop(&self, origin: (usize, usize, usize), sizes: &GridSizes) -> VoxelGrid {
+        let x_len = sizes.x_len.min(self.sizes.x_len.saturating_sub(origin.0));
+        let y_len = sizes.y_len.min(self.sizes.y_len.saturating_sub(origin.1));
+        let z_len = sizes.z_len.min(self.sizes.z_len.saturating_sub(origin.2));
+
+        let mut cropped_grid = VoxelGrid::new(x_len, y_len, z_len);
+        for z in 0..z_len {
+            for y in 0..y_len {
+                for x in 0..x_len {
+                    if self.get(origin.0 + x, origin.1 + y, origin.2 + z) {
+                        cropped_grid.set(x, y, z, true);
+                    }
+                }
+            }
+        }
+
+        cropped_grid
+    }
+
+    /// Returns the grid in the nested grid[x][y][z] shape used before
+    /// VoxelGrid existed.
+    pub fn to_nested(&self) -> Vec<Vec<Vec<bool>>> {
+        (0..self.sizes.x_len)
+            .map(|x| {
+                (0..self.sizes.y_len)
+                    .map(|y| (0..self.sizes.z_len).map(|z| self.get(x, y, z)).collect())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl PartialEq for VoxelGrid {
+    /// Grids are equal when the same voxels are filled, whether they
+    /// are dense or sparse.
+    fn eq(&self, other: &Self) -> bool {
+        if self.sizes != other.sizes {
+            return false;
+        }
+
+        (0..self.sizes.z_len).all(|z| {
+            (0..self.sizes.y_len)
+                .all(|y| (0..self.sizes.x_len).all(|x| self.get(x, y, z) == other.get(x, y, z)))
+        })
+    }
+}
+
+impl From<&[Vec<Vec<bool>>]> for VoxelGrid {
+    fn from(nested_grid: &[Vec<Vec<bool>>]) -> Self {
+        let x_len = nested_grid.len();
+        let y_len = nested_grid.first().map_or(0, |plane| plane.len());
+        let z_len = nested_grid
+            .first()
+            .and_then(|plane| plane.first())
+            .map_or(0, |column| column.len());
+
+        let mut voxel_grid = VoxelGrid::new(x_len, y_len, z_len);
+        for (x, plane) in nested_grid.iter().enumerate() {
+            for (y, column) in plane.iter().enumerate() {
+                for (z, voxel) in column.iter().enumerate() {
+                    voxel_grid.set(x, y, z, *voxel);
+                }
+            }
+        }
+
+        voxel_grid
+    }
+}
+
+impl From<Vec<Vec<Vec<bool>>>> for VoxelGrid {
+    fn from(nested_grid: Vec<Vec<Vec<bool>>>) -> Self {
+        VoxelGrid::from(nested_grid.as_slice())
+    }
+}
+
+impl From<&VoxelGrid> for Vec<Vec<Vec<bool>>> {
+    fn from(voxel_grid: &VoxelGrid) -> Self {
+        voxel_grid.to_nested()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get_across_words() {
+        let mut voxel_grid = VoxelGrid::new(5, 5, 5);
+        voxel_grid.set(4, 4, 2, true);
+        voxel_grid.set(0, 0, 0, true);
+        voxel_grid.set(0, 0, 0, false);
+
+        assert!(voxel_grid.get(4, 4, 2));
+        assert!(!voxel_grid.get(0, 0, 0));
+        assert_eq!(voxel_grid.filled_count(), 1);
+    }
+
+    #[test]
+    fn nested_round_trip() {
+        let mut nested_grid = vec![vec![vec![false; 3]; 2]; 4];
+        nested_grid[3][1][2] = true;
+        nested_grid[1][0][1] = true;
+
+        let voxel_grid = VoxelGrid::from(nested_grid.clone());
+
+        assert!(voxel_grid.get(3, 1, 2));
+        assert_eq!(voxel_grid.to_nested(), nested_grid);
+    }
+
+    #[test]
+    fn crop_stops_at_edge() {
+        let mut voxel_grid = VoxelGrid::new(4, 4, 4);
+        voxel_grid.set(3, 2, 1, true);
+        let sizes = GridSizes {
+            x_len: 3,
+            y_len: 3,
+            z_len: 3,
+        };
+
+        let cropped_grid = voxel_grid.crop((2, 2, 0), &sizes);
+
+        assert_eq!(
+            *cropped_grid.size(),
+            GridSizes {
+                x_len: 2,
+                y_len: 2,
+                z_len: 3
+            }
+        );
+        assert!(cropped_grid.get(1, 0, 1));
+        assert_eq!(cropped_grid.filled_count(), 1);
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        let mut dense_grid = VoxelGrid::new(40, 20, 33);
+        let mut sparse_grid = VoxelGrid::new_sparse(40, 20, 33);
+        for (x, y, z) in [(0, 0, 0), (39, 19, 32), (17, 3, 16), (17, 3, 16)] {
+            dense_grid.set(x, y, z, true);
+            sparse_grid.set(x, y, z, true);
+        }
+        sparse_grid.set(20, 10, 10, false);
+
+        assert!(sparse_grid.is_sparse());
+        assert_eq!(sparse_grid.filled_count(), 3);
+        assert!(sparse_grid.get(39, 19, 32));
+        assert!(!sparse_grid.get(38, 19, 32));
+        assert_eq!(dense_grid, sparse_grid);
+    }
+
+    #[test]
+    fn sparse_only_stores_filled_chunks() {
+        let mut sparse_grid = VoxelGrid::new_sparse(2000, 2000, 256);
+        sparse_grid.set(1999, 1999, 255, true);
+        sparse_grid.set(1000, 3, 0, false);
+
+        let Voxels::Chunked { chunks, .. } = &sparse_grid.voxels else {
+            panic!("Grid is not sparse.");
+        };
+        assert_eq!(chunks.iter().flatten().count(), 1);
+        assert_eq!(sparse_grid.filled_count(), 1);
+    }
+
+    #[test]
+    fn empty_within_region() {
+        let region = GridSizes {
+            x_len: 20,
+            y_len: 20,
+            z_len: 20,
+        };
+
+        for mut voxel_grid in [
+            VoxelGrid::new(40, 40, 40),
+            VoxelGrid::new_sparse(40, 40, 40),
+        ] {
+            voxel_grid.set(20, 5, 5, true);
+
+            assert!(voxel_grid.is_empty_within((0, 0, 0), &region));
+            assert!(!voxel_grid.is_empty_within((15, 0, 0), &region));
+            assert!(voxel_grid.is_empty_within((21, 0, 0), &region));
+        }
+    }
+
+    #[test]
+    fn outside_is_empty() {
+        let mut voxel_grid = VoxelGrid::new(1, 1, 1);
+        voxel_grid.set(0, 0, 0, true);
+
+        assert!(voxel_grid.get_or_empty(0, 0, 0));
+        assert!(!voxel_grid.get_or_empty(-1, 0, 0));
+        assert!(!voxel_grid.get_or_empty(0, 1, 0));
+    }
+}
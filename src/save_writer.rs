@@ -0,0 +1,75 @@
+use std::io::{self, Write};
+
+use crate::blockland::mapping::{Elevation, Origin};
+use crate::blockland::save_file::BlsWriter;
+use crate::blockland::Brick;
+use crate::brickadia::save_file::BrsWriter;
+use crate::decomposition::Cuboid;
+
+/// A save file format bricks can be written out as.
+///
+/// Implement this to write bricks somewhere other than a Blockland
+/// save file, without having to change how bricks are found.
+pub trait SaveWriter {
+    /// Returns the name used to pick this format from the CLI.
+    fn name(&self) -> &str;
+
+    /// Returns the extension files of this format are given, without
+    /// the leading dot.
+    fn extension(&self) -> &str;
+
+    fn write_bricks(&self, bricks: &[Brick], writer: &mut dyn Write) -> io::Result<()>;
+
+    /// Returns whether bricks of this format can be any size, in which
+    /// case the boxes a Decomposer finds are written whole with
+    /// write_cuboids instead of being split into cubes.
+    fn writes_cuboids(&self) -> bool {
+        false
+    }
+
+    /// Writes the boxes a Decomposer found as one brick each, placed
+    /// the same way BrickBuilder places bricks.
+    fn write_cuboids(
+        &self,
+        _cuboids: &[Cuboid],
+        _origin: Origin,
+        _elevation: Elevation,
+        _writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} save files cannot hold bricks of any size", self.name()),
+        ))
+    }
+}
+
+/// Returns every save writer that comes with schematic2bls, with the
+/// default one first.
+pub fn built_in_save_writers() -> Vec<Box<dyn SaveWriter>> {
+    vec![Box::new(BlsWriter::default()), Box::new(BrsWriter)]
+}
+
+pub fn built_in_save_writer_named(name: &str) -> Option<Box<dyn SaveWriter>> {
+    built_in_save_writers()
+        .into_iter()
+        .find(|save_writer| save_writer.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_save_writer_by_name() {
+        let save_writer = built_in_save_writer_named("bls").unwrap();
+
+        assert_eq!(save_writer.extension(), "bls");
+        assert_eq!(
+            built_in_save_writer_named("brs").unwrap().extension(),
+            "brs"
+        );
+        assert!(built_in_save_writer_named("obj").is_none());
+        assert!(!save_writer.writes_cuboids());
+        assert!(built_in_save_writer_named("brs").unwrap().writes_cuboids());
+    }
+}
@@ -0,0 +1,199 @@
+use super::{Facing, Slope};
+use crate::model::volume::VoxelGrid;
+
+/// Returns the direction a ramp placed in this empty voxel would
+/// rise toward, or None if the voxel is not the inner corner of
+/// a single step.
+///
+/// A step corner rests on a filled voxel, has exactly one filled
+/// neighbor beside it (the riser), and nothing on top of the riser.
+fn step_corner_at(grid: &VoxelGrid, x: isize, y: isize, z: isize) -> Option<Facing> {
+    if grid.get_or_empty(x, y, z) || !grid.get_or_empty(x, y, z - 1) {
+        return None;
+    }
+
+    let mut risers = Facing::ALL.iter().filter(|facing| {
+        let (dx, dy) = facing.offset();
+        grid.get_or_empty(x + dx, y + dy, z)
+    });
+
+    let riser = *risers.next()?;
+    if risers.next().is_some() {
+        return None;
+    }
+
+    let (dx, dy) = riser.offset();
+    if grid.get_or_empty(x + dx, y + dy, z + 1) {
+        return None;
+    }
+
+    Some(riser)
+}
+
+/// Returns whether this empty voxel rests on a filled voxel with
+/// nothing filled beside it.
+fn bare_tread_at(grid: &VoxelGrid, x: isize, y: isize, z: isize) -> bool {
+    let has_neighbor = Facing::ALL.iter().any(|facing| {
+        let (dx, dy) = facing.offset();
+        grid.get_or_empty(x + dx, y + dy, z)
+    });
+
+    !grid.get_or_empty(x, y, z) && grid.get_or_empty(x, y, z - 1) && !has_neighbor
+}
+
+/// Returns ramps that smooth out staircase patterns found on the
+/// surface of the voxel grid.
+///
+/// Steps that go up by one voxel every voxel become ramps, and steps
+/// that go up by one voxel every two voxels become long ramps. A step
+/// only becomes a ramp when the step next to it, either one down
+/// behind it or one up in front of it, has the same shape, so lone
+/// ledges are left alone.
+pub fn find_stair_steps(grid: &VoxelGrid, scale: u16) -> Vec<Slope> {
+    let mut slopes = Vec::new();
+
+    let sizes = grid.size();
+    for i in 0..sizes.x_len {
+        for j in 0..sizes.y_len {
+            for k in 0..sizes.z_len {
+                let (x, y, z) = (i as isize, j as isize, k as isize);
+                let Some(rises_toward) = step_corner_at(grid, x, y, z) else {
+                    continue;
+                };
+
+                let (dx, dy) = rises_toward.offset();
+                let continues_with = |run: isize| {
+                    let previous_step = step_corner_at(grid, x - dx * run, y - dy * run, z - 1);
+                    let next_step = step_corner_at(grid, x + dx * run, y + dy * run, z + 1);
+
+                    previous_step == Some(rises_toward) || next_step == Some(rises_toward)
+                };
+
+                let run = if continues_with(1) {
+                    1
+                } else if bare_tread_at(grid, x - dx, y - dy, z) && continues_with(2) {
+                    2
+                } else {
+                    continue;
+                };
+
+                // The ramp covers this voxel and the tread behind it
+                // for long ramps, so its corner is whichever of the
+                // two is furthest along each axis.
+                let tread = ((x - dx * (run - 1)) as usize, (y - dy * (run - 1)) as usize);
+                let scale = scale as usize;
+                slopes.push(Slope {
+                    side_length: scale as u16,
+                    indexes: (
+                        (i.max(tread.0) + 1) * scale,
+                        (j.max(tread.1) + 1) * scale,
+                        (k + 1) * scale,
+                    ),
+                    rises_toward,
+                    run: run as usize,
+                });
+            }
+        }
+    }
+
+    slopes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a grid of columns along X whose heights are given,
+    /// one voxel deep along Y.
+    fn staircase(heights: &[usize]) -> VoxelGrid {
+        let max_height = heights.iter().max().copied().unwrap_or(0) + 1;
+
+        let nested_grid = heights
+            .iter()
+            .map(|height| vec![(0..max_height).map(|k| k < *height).collect()])
+            .collect::<Vec<Vec<Vec<bool>>>>();
+        VoxelGrid::from(nested_grid)
+    }
+
+    #[test]
+    fn ramps_on_staircase() {
+        let grid = staircase(&[1, 2, 3]);
+
+        let expected = vec![
+            Slope {
+                side_length: 4,
+                indexes: (4, 4, 8),
+                rises_toward: Facing::PositiveX,
+                run: 1,
+            },
+            Slope {
+                side_length: 4,
+                indexes: (8, 4, 12),
+                rises_toward: Facing::PositiveX,
+                run: 1,
+            },
+        ];
+
+        let actual = find_stair_steps(&grid, 4);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn ramps_on_descending_staircase() {
+        let grid = staircase(&[3, 2, 1]);
+
+        let actual = find_stair_steps(&grid, 1);
+
+        assert_eq!(actual.len(), 2);
+        assert!(actual
+            .iter()
+            .all(|slope| slope.rises_toward == Facing::NegativeX));
+    }
+
+    #[test]
+    fn no_ramp_on_single_ledge() {
+        let grid = staircase(&[1, 1, 2, 2]);
+
+        let actual = find_stair_steps(&grid, 1);
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn long_ramps_on_gentle_staircase() {
+        let grid = staircase(&[1, 1, 2, 2, 3, 3]);
+
+        let expected = vec![
+            Slope {
+                side_length: 1,
+                indexes: (2, 1, 2),
+                rises_toward: Facing::PositiveX,
+                run: 2,
+            },
+            Slope {
+                side_length: 1,
+                indexes: (4, 1, 3),
+                rises_toward: Facing::PositiveX,
+                run: 2,
+            },
+        ];
+
+        let actual = find_stair_steps(&grid, 1);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn long_ramps_on_descending_gentle_staircase() {
+        let grid = staircase(&[3, 3, 2, 2, 1, 1]);
+
+        let actual = find_stair_steps(&grid, 1);
+
+        let corners = actual
+            .iter()
+            .map(|slope| (slope.indexes, slope.run))
+            .collect::<Vec<_>>();
+        assert_eq!(corners, vec![((4, 1, 3), 2), ((6, 1, 2), 2)]);
+    }
+}
@@ -0,0 +1,53 @@
+pub mod detection;
+
+/// A direction along the ground, relative to the voxel grid.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Facing {
+    PositiveX,
+    PositiveY,
+    NegativeX,
+    NegativeY,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [
+        Facing::PositiveX,
+        Facing::PositiveY,
+        Facing::NegativeX,
+        Facing::NegativeY,
+    ];
+
+    /// Returns how far one step in this direction moves along
+    /// the X and Y axes.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Facing::PositiveX => (1, 0),
+            Facing::PositiveY => (0, 1),
+            Facing::NegativeX => (-1, 0),
+            Facing::NegativeY => (0, -1),
+        }
+    }
+
+    pub fn opposite(&self) -> Facing {
+        match self {
+            Facing::PositiveX => Facing::NegativeX,
+            Facing::PositiveY => Facing::NegativeY,
+            Facing::NegativeX => Facing::PositiveX,
+            Facing::NegativeY => Facing::PositiveY,
+        }
+    }
+}
+
+/// A ramp as wide and tall as a cube of side_length, where indexes
+/// follow the same convention as a LargestCube, but for the corner
+/// of everything the ramp covers.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Slope {
+    pub side_length: u16,
+    pub indexes: (usize, usize, usize),
+    /// The direction in which the ramp goes up.
+    pub rises_toward: Facing,
+    /// How many voxels the ramp covers along rises_toward to go up
+    /// by one voxel, which is either 1 or 2.
+    pub run: usize,
+}
@@ -0,0 +1,117 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::blockland::mapping::{Elevation, ModelBounds, Origin};
+use crate::blockland::save_file::BlsWriter;
+use crate::blockland::Brick;
+use crate::largest_cube::tiling::{get_largest_cubes_tiled, tile_side_for};
+use crate::largest_cube::{CubeSizes, LargestCube};
+use crate::model::slabs::SchematicSlabs;
+
+/// How many bytes one Largest Cube takes up in the scratch file.
+const CUBE_RECORD_LEN: usize = 2 + 3 * 8;
+
+fn write_cube_record(writer: &mut impl Write, largest_cube: &LargestCube) -> io::Result<()> {
+    writer.write_all(&largest_cube.side_length.to_le_bytes())?;
+    writer.write_all(&(largest_cube.indexes.0 as u64).to_le_bytes())?;
+    writer.write_all(&(largest_cube.indexes.1 as u64).to_le_bytes())?;
+    writer.write_all(&(largest_cube.indexes.2 as u64).to_le_bytes())
+}
+
+fn read_cube_record(reader: &mut impl Read) -> io::Result<LargestCube> {
+    let mut record = [0; CUBE_RECORD_LEN];
+    reader.read_exact(&mut record)?;
+
+    let index_at = |offset: usize| {
+        let mut index_bytes = [0; 8];
+        index_bytes.copy_from_slice(&record[offset..offset + 8]);
+        u64::from_le_bytes(index_bytes) as usize
+    };
+
+    Ok(LargestCube {
+        side_length: u16::from_le_bytes([record[0], record[1]]),
+        indexes: (index_at(2), index_at(10), index_at(18)),
+    })
+}
+
+/// A file for holding onto things in between steps, which is deleted
+/// once it goes out of scope, whether or not those steps succeeded.
+struct ScratchFile {
+    path: PathBuf,
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        // There is nothing left to clean up if the file never got
+        // created.
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Converts a schematic into a save file one slab at a time, and
+/// returns how many bricks were written.
+///
+/// Only one slab of the model is held in memory at once, where each
+/// slab is as thick as the tiles used by get_largest_cubes_tiled, so
+/// the same cubes are found as when converting the whole model at
+/// once. Cubes are kept in a scratch file next to the save file until
+/// every slab has been read, since placing bricks depends on the
+/// bounding box of the whole model, and deleted afterward even if
+/// something went wrong.
+#[allow(clippy::too_many_arguments)]
+pub fn stream_schematic_to_save_file(
+    schematic_path: &Path,
+    save_file_path: &Path,
+    scale: u16,
+    cube_sizes: &CubeSizes,
+    threads: usize,
+    origin: Origin,
+    elevation: Elevation,
+    bls_writer: &BlsWriter,
+) -> io::Result<usize> {
+    let mut slabs = SchematicSlabs::open(schematic_path)?;
+    let slab_thickness = tile_side_for(cube_sizes, scale);
+
+    let mut scratch_path = save_file_path.as_os_str().to_os_string();
+    scratch_path.push(".part");
+    let scratch = ScratchFile {
+        path: PathBuf::from(scratch_path),
+    };
+    let mut scratch_file = BufWriter::new(File::create(&scratch.path)?);
+
+    let mut cube_count = 0;
+    let mut model_bounds: Option<ModelBounds> = None;
+    while let Some((first_layer, slab)) = slabs.next_slab(slab_thickness)? {
+        for mut largest_cube in get_largest_cubes_tiled(&slab, scale, cube_sizes, threads) {
+            largest_cube.indexes.2 += first_layer * scale as usize;
+            write_cube_record(&mut scratch_file, &largest_cube)?;
+            cube_count += 1;
+
+            let brick = Brick::new(largest_cube.indexes, largest_cube.side_length);
+            match model_bounds.as_mut() {
+                Some(model_bounds) => model_bounds.include(&brick),
+                None => model_bounds = ModelBounds::of([&brick]),
+            }
+        }
+    }
+    scratch_file.flush()?;
+    drop(scratch_file);
+
+    let mut save_file = BufWriter::new(File::create(save_file_path)?);
+    bls_writer.write_header(&mut save_file, cube_count)?;
+
+    if let Some(model_bounds) = model_bounds {
+        let anchor = model_bounds.anchor(origin, elevation);
+        let mut scratch_file = BufReader::new(File::open(&scratch.path)?);
+        for _ in 0..cube_count {
+            let largest_cube = read_cube_record(&mut scratch_file)?;
+            let brick = Brick::new(largest_cube.indexes, largest_cube.side_length)
+                .calculate_right_offset(anchor);
+            bls_writer.write_brick(&mut save_file, &brick)?;
+        }
+    }
+    save_file.flush()?;
+
+    Ok(cube_count)
+}
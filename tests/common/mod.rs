@@ -1,3 +1,9 @@
+#![allow(dead_code)]
+
+use schematic2bls::decomposition::Cuboid;
+use schematic2bls::model::volume::VoxelGrid;
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Copy, Clone)]
 pub enum Direction {
     XAxis,
@@ -11,7 +17,7 @@ pub enum Direction {
 /// A mock-based tool used to create 3D Grids
 /// compatible with the Maximal Cube algorithm.
 pub struct CubePainter {
-    grid: Vec<Vec<Vec<bool>>>,
+    grid: VoxelGrid,
     next_idx: (usize, usize, usize),
 }
 
@@ -20,7 +26,7 @@ impl CubePainter {
         let grid_size = cube_sizes.iter().sum();
 
         CubePainter {
-            grid: vec![vec![vec![false; grid_size]; grid_size]; grid_size],
+            grid: VoxelGrid::new(grid_size, grid_size, grid_size),
             next_idx: (0, 0, 0),
         }
     }
@@ -39,7 +45,7 @@ impl CubePainter {
         for i in x..x + cube_size {
             for j in y..y + cube_size {
                 for k in z..z + cube_size {
-                    self.grid[i][j][k] = true;
+                    self.grid.set(i, j, k, true);
                 }
             }
         }
@@ -103,7 +109,43 @@ impl CubePainter {
         }
     }
 
-    pub fn to_grid(&self) -> Vec<Vec<Vec<bool>>> {
+    pub fn to_grid(&self) -> VoxelGrid {
         self.grid.clone()
     }
 }
+
+/// Returns whether the cuboids cover every filled voxel of the grid
+/// exactly once, and none of the empty ones.
+pub fn covers_exactly(grid: &VoxelGrid, cuboids: &[Cuboid], scale: usize) -> bool {
+    let sizes = grid.size();
+    let mut claimed = vec![vec![vec![0; sizes.z_len]; sizes.y_len]; sizes.x_len];
+
+    for cuboid in cuboids {
+        let (x_len, y_len, z_len) = cuboid.dimensions;
+        let end = (
+            cuboid.indexes.0 / scale,
+            cuboid.indexes.1 / scale,
+            cuboid.indexes.2 / scale,
+        );
+        let start = (
+            end.0 - x_len as usize / scale,
+            end.1 - y_len as usize / scale,
+            end.2 - z_len as usize / scale,
+        );
+
+        for plane in &mut claimed[start.0..end.0] {
+            for column in &mut plane[start.1..end.1] {
+                for voxel in &mut column[start.2..end.2] {
+                    *voxel += 1;
+                }
+            }
+        }
+    }
+
+    grid.to_nested()
+        .iter()
+        .flatten()
+        .flatten()
+        .zip(claimed.iter().flatten().flatten())
+        .all(|(filled, claims)| (*filled && *claims == 1) || (!*filled && *claims == 0))
+}
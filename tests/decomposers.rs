@@ -0,0 +1,114 @@
+mod common;
+
+use crate::common::*;
+use crate::decomposition::{built_in_decomposers, Cuboid, Decomposer};
+use crate::largest_cube::CubeSizes;
+use crate::model::volume::VoxelGrid;
+use schematic2bls::*;
+
+/// A stand-in for a house algorithm, which claims every voxel on its
+/// own, to make sure outside decomposers run through the same harness.
+struct SingleVoxels;
+
+impl Decomposer for SingleVoxels {
+    fn name(&self) -> &str {
+        "single-voxels"
+    }
+
+    fn decompose(&self, voxel_grid: &VoxelGrid, scale: u16) -> Vec<Cuboid> {
+        let mut cuboids = Vec::new();
+        let scale_usize = scale as usize;
+
+        let sizes = voxel_grid.size();
+        for i in 0..sizes.x_len {
+            for j in 0..sizes.y_len {
+                for k in 0..sizes.z_len {
+                    if !voxel_grid.get(i, j, k) {
+                        continue;
+                    }
+
+                    cuboids.push(Cuboid {
+                        dimensions: (scale, scale, scale),
+                        indexes: (
+                            (i + 1) * scale_usize,
+                            (j + 1) * scale_usize,
+                            (k + 1) * scale_usize,
+                        ),
+                    });
+                }
+            }
+        }
+
+        cuboids
+    }
+}
+
+fn every_decomposer() -> Vec<Box<dyn Decomposer>> {
+    let mut decomposers = built_in_decomposers(&CubeSizes::default(), 2);
+    decomposers.push(Box::new(SingleVoxels));
+
+    decomposers
+}
+
+fn assert_every_decomposer_covers(grid: &VoxelGrid, scale: u16) {
+    for decomposer in every_decomposer() {
+        let cuboids = extract_cuboids_with(grid, scale, decomposer.as_ref());
+
+        assert!(
+            covers_exactly(grid, &cuboids, scale as usize),
+            "{} did not cover the grid exactly",
+            decomposer.name()
+        );
+    }
+}
+
+#[test]
+fn decomposers_cover_cubes_along_each_axis() {
+    let cube_sizes = [1, 2, 4];
+
+    for direction in [Direction::XAxis, Direction::YAxis, Direction::ZAxis] {
+        let mut cube_painter = CubePainter::new(&cube_sizes);
+        for cube_size in cube_sizes.iter() {
+            cube_painter.draw(direction, *cube_size);
+        }
+
+        assert_every_decomposer_covers(&cube_painter.to_grid(), 4);
+    }
+}
+
+#[test]
+fn decomposers_cover_overlapping_cubes() {
+    let cube_sizes = [4, 2];
+    let direction = Direction::XYAxis;
+
+    let mut cube_painter = CubePainter::new(&cube_sizes);
+    for cube_size in cube_sizes.iter() {
+        cube_painter.draw(direction, *cube_size);
+        cube_painter.shift(direction, -1);
+    }
+
+    assert_every_decomposer_covers(&cube_painter.to_grid(), 1);
+}
+
+#[test]
+fn decomposers_cover_nothing_in_empty_grid() {
+    let grid = VoxelGrid::new(3, 3, 3);
+
+    for decomposer in every_decomposer() {
+        let cuboids = extract_cuboids_with(&grid, 4, decomposer.as_ref());
+
+        assert!(cuboids.is_empty(), "{} found cuboids", decomposer.name());
+    }
+}
+
+#[test]
+fn decomposer_names_are_unique() {
+    let mut names = every_decomposer()
+        .iter()
+        .map(|decomposer| decomposer.name().to_string())
+        .collect::<Vec<String>>();
+    names.sort();
+    names.dedup();
+
+    assert_eq!(names.len(), every_decomposer().len());
+}
@@ -1,5 +1,6 @@
 use crate::common::*;
 use crate::largest_cube::LargestCube;
+use crate::model::volume::VoxelGrid;
 use schematic2bls::*;
 
 mod common;
@@ -11,8 +12,8 @@ const BLOCKS_FOUR: [usize; 4] = [1, 2, 4, 8];
 const BLOCKS_FIVE: [usize; 5] = [1, 2, 4, 8, 16];
 const BLOCKS_SIX: [usize; 6] = [1, 2, 4, 8, 16, 32];
 
-fn generate_grid_from(cube_sizes: &[usize], direction: Direction) -> Vec<Vec<Vec<bool>>> {
-    let mut cube_painter = CubePainter::new(&cube_sizes);
+fn generate_grid_from(cube_sizes: &[usize], direction: Direction) -> VoxelGrid {
+    let mut cube_painter = CubePainter::new(cube_sizes);
     for cube_size in cube_sizes.iter() {
         cube_painter.draw(direction, *cube_size);
     }
@@ -21,7 +22,7 @@ fn generate_grid_from(cube_sizes: &[usize], direction: Direction) -> Vec<Vec<Vec
 }
 
 fn get_expected_side_lengths_from(cube_sizes: &[usize]) -> Vec<usize> {
-    let mut expected_side_lengths = cube_sizes.iter().cloned().collect::<Vec<usize>>();
+    let mut expected_side_lengths = cube_sizes.to_vec();
     expected_side_lengths.sort_unstable();
     expected_side_lengths.reverse();
 
@@ -141,7 +142,18 @@ fn case9() {}
 
 //Test Case 10  		(Key = 1.4.1.2.)
 #[test]
-fn case10() {}
+fn case10() {
+    let cube_sizes = BLOCKS_THREE;
+    let direction = Direction::XAxis;
+
+    let grid = generate_grid_from(&cube_sizes, direction);
+    let largest_cubes = extract_largest_cubes_from(grid, 1);
+
+    let expected_side_lengths = get_expected_side_lengths_from(&cube_sizes);
+    assert_eq!(largest_cubes.len(), expected_side_lengths.len());
+
+    assert_largest_cubes_match(&largest_cubes, &expected_side_lengths);
+}
 
 //Test Case 11  		(Key = 1.4.2.1.)
 #[test]
@@ -149,7 +161,18 @@ fn case11() {}
 
 //Test Case 12  		(Key = 1.4.2.2.)
 #[test]
-fn case12() {}
+fn case12() {
+    let cube_sizes = BLOCKS_THREE;
+    let direction = Direction::ZAxis;
+
+    let grid = generate_grid_from(&cube_sizes, direction);
+    let largest_cubes = extract_largest_cubes_from(grid, 1);
+
+    let expected_side_lengths = get_expected_side_lengths_from(&cube_sizes);
+    assert_eq!(largest_cubes.len(), expected_side_lengths.len());
+
+    assert_largest_cubes_match(&largest_cubes, &expected_side_lengths);
+}
 
 //Test Case 13  		(Key = 1.4.3.1.)
 #[test]
@@ -165,7 +188,18 @@ fn case15() {}
 
 //Test Case 16  		(Key = 1.5.1.2.)
 #[test]
-fn case16() {}
+fn case16() {
+    let cube_sizes = BLOCKS_FOUR;
+    let direction = Direction::XAxis;
+
+    let grid = generate_grid_from(&cube_sizes, direction);
+    let largest_cubes = extract_largest_cubes_from(grid, 1);
+
+    let expected_side_lengths = get_expected_side_lengths_from(&cube_sizes);
+    assert_eq!(largest_cubes.len(), expected_side_lengths.len());
+
+    assert_largest_cubes_match(&largest_cubes, &expected_side_lengths);
+}
 
 //Test Case 17  		(Key = 1.5.2.1.)
 #[test]
@@ -173,7 +207,18 @@ fn case17() {}
 
 //Test Case 18  		(Key = 1.5.2.2.)
 #[test]
-fn case18() {}
+fn case18() {
+    let cube_sizes = BLOCKS_FOUR;
+    let direction = Direction::ZAxis;
+
+    let grid = generate_grid_from(&cube_sizes, direction);
+    let largest_cubes = extract_largest_cubes_from(grid, 1);
+
+    let expected_side_lengths = get_expected_side_lengths_from(&cube_sizes);
+    assert_eq!(largest_cubes.len(), expected_side_lengths.len());
+
+    assert_largest_cubes_match(&largest_cubes, &expected_side_lengths);
+}
 
 //Test Case 19  		(Key = 1.5.3.1.)
 #[test]
@@ -189,7 +234,18 @@ fn case21() {}
 
 //Test Case 22  		(Key = 1.6.1.2.)
 #[test]
-fn case22() {}
+fn case22() {
+    let cube_sizes = BLOCKS_FIVE;
+    let direction = Direction::XAxis;
+
+    let grid = generate_grid_from(&cube_sizes, direction);
+    let largest_cubes = extract_largest_cubes_from(grid, 1);
+
+    let expected_side_lengths = get_expected_side_lengths_from(&cube_sizes);
+    assert_eq!(largest_cubes.len(), expected_side_lengths.len());
+
+    assert_largest_cubes_match(&largest_cubes, &expected_side_lengths);
+}
 
 //Test Case 23  		(Key = 1.6.2.1.)
 #[test]
@@ -197,7 +253,18 @@ fn case23() {}
 
 //Test Case 24  		(Key = 1.6.2.2.)
 #[test]
-fn case24() {}
+fn case24() {
+    let cube_sizes = BLOCKS_FIVE;
+    let direction = Direction::ZAxis;
+
+    let grid = generate_grid_from(&cube_sizes, direction);
+    let largest_cubes = extract_largest_cubes_from(grid, 1);
+
+    let expected_side_lengths = get_expected_side_lengths_from(&cube_sizes);
+    assert_eq!(largest_cubes.len(), expected_side_lengths.len());
+
+    assert_largest_cubes_match(&largest_cubes, &expected_side_lengths);
+}
 
 //Test Case 25  		(Key = 1.6.3.1.)
 #[test]
@@ -1,7 +1,20 @@
 mod common;
 
-use crate::blockland::save_file::to_save_file_output;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::blockland::catalog::BrickCatalog;
+use crate::blockland::events::interactive_block_rules;
+use crate::blockland::mapping::{Elevation, Origin};
+use crate::blockland::naming::NamingRule;
+use crate::blockland::ownership::BrickOwners;
+use crate::blockland::parsing::{parse_save_file, BrickAttribute};
+use crate::blockland::save_file::{to_save_file_output, BlsWriter};
 use crate::common::*;
+use crate::model::conversion::grid_to_schematic;
+use crate::model::volume::VoxelGrid;
+use crate::save_writer::SaveWriter;
+use nbt::CompoundTag;
 use schematic2bls::*;
 
 /// Returns a Blockland Save File formatted String based on what
@@ -19,8 +32,7 @@ fn paint_to_bricks(cube_sizes: &[usize], direction: Direction) -> String {
 
     let grid = cube_painter.to_grid();
     let largest_cubes = extract_largest_cubes_from(grid, scaling_factor);
-    let mut bricks = extract_bricks_from(largest_cubes);
-    bricks.sort_by(|brick1, brick2| brick1.position.partial_cmp(&brick2.position).unwrap());
+    let bricks = extract_bricks_from(largest_cubes);
 
     to_save_file_output(&bricks)
 }
@@ -43,8 +55,7 @@ fn paint_with_overlap(cube_sizes: &[usize], direction: Direction) -> String {
 
     let grid = cube_painter.to_grid();
     let largest_cubes = extract_largest_cubes_from(grid, scaling_factor);
-    let mut bricks = extract_bricks_from(largest_cubes);
-    bricks.sort_by(|brick1, brick2| brick1.position.partial_cmp(&brick2.position).unwrap());
+    let bricks = extract_bricks_from(largest_cubes);
 
     to_save_file_output(&bricks)
 }
@@ -59,6 +70,176 @@ fn place_one_4x_cube() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn write_4x_cube_into_buffer() {
+    let expected = include_str!("../assets/brick_comparisons/4xCube.bls");
+    let mut cube_painter = CubePainter::new(&[1]);
+    cube_painter.draw(Direction::XAxis, 1);
+    let bricks = extract_bricks_from(extract_largest_cubes_from(cube_painter.to_grid(), 4));
+
+    let mut actual = Vec::new();
+    BlsWriter::default()
+        .write_bricks(&bricks, &mut actual)
+        .unwrap();
+
+    assert_eq!(expected.as_bytes(), actual.as_slice());
+}
+
+#[test]
+fn write_bricks_with_owner() {
+    let public_save_file = include_str!("../assets/brick_comparisons/4xCube.bls");
+    let mut cube_painter = CubePainter::new(&[1]);
+    cube_painter.draw(Direction::XAxis, 1);
+    let bricks = extract_bricks_from(extract_largest_cubes_from(cube_painter.to_grid(), 4));
+
+    let mut owned = Vec::new();
+    let bls_writer = BlsWriter {
+        brick_owners: BrickOwners::owned_by(1234),
+        ..BlsWriter::default()
+    };
+    bls_writer.write_bricks(&bricks, &mut owned).unwrap();
+    let mut unowned = Vec::new();
+    let bls_writer = BlsWriter {
+        brick_owners: BrickOwners::unowned(),
+        ..BlsWriter::default()
+    };
+    bls_writer.write_bricks(&bricks, &mut unowned).unwrap();
+
+    let expected_owned = public_save_file.replace("+-OWNER 999999", "+-OWNER 1234");
+    let expected_unowned = public_save_file.replace("+-OWNER 999999\r\n", "");
+    assert_eq!(expected_owned, String::from_utf8(owned).unwrap());
+    assert_eq!(expected_unowned, String::from_utf8(unowned).unwrap());
+}
+
+#[test]
+fn write_4x_cube_as_brickadia_save_file() {
+    let mut cube_painter = CubePainter::new(&[1]);
+    cube_painter.draw(Direction::XAxis, 1);
+    let bricks = extract_bricks_from(extract_largest_cubes_from(cube_painter.to_grid(), 4));
+    let save_writer = save_writer::built_in_save_writer_named("brs").unwrap();
+
+    let mut actual = Vec::new();
+    save_writer.write_bricks(&bricks, &mut actual).unwrap();
+
+    assert_eq!(save_writer.extension(), "brs");
+    assert_eq!(&actual[..5], b"BRS\x0a\x00");
+}
+
+#[test]
+fn set_up_events_on_doors_and_buttons() {
+    // A row of stone, an oak door and a stone button.
+    let mut schematic_root = CompoundTag::new();
+    schematic_root.insert_i16("Length", 1);
+    schematic_root.insert_i16("Width", 3);
+    schematic_root.insert_i16("Height", 1);
+    schematic_root.insert_i8_vec("Blocks", vec![1, 64, 77]);
+    schematic_root.insert_i8_vec("Data", vec![0; 3]);
+    let shaped_blocks = parse_shaped_blocks_from_model(&schematic_root, 4);
+    let largest_cubes = extract_largest_cubes_from(parse_grid_from_model(schematic_root), 4);
+    let bricks = extract_bricks_with_blocks_placed_from(
+        largest_cubes,
+        Vec::new(),
+        shaped_blocks.interactive_blocks,
+        Origin::Corner,
+        Elevation::PlaceOnGround,
+    );
+    let bls_writer = BlsWriter {
+        event_rules: interactive_block_rules(),
+        ..BlsWriter::default()
+    };
+
+    let mut contents = Vec::new();
+    bls_writer.write_bricks(&bricks, &mut contents).unwrap();
+
+    let save_file = parse_save_file(&String::from_utf8(contents).unwrap()).unwrap();
+    let output_events: Vec<Vec<String>> = save_file
+        .bricks
+        .iter()
+        .map(|saved_brick| {
+            saved_brick
+                .attributes
+                .iter()
+                .filter_map(|attribute| match attribute {
+                    BrickAttribute::Event(brick_event) => Some(brick_event.output_event.clone()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+    let expected = vec![
+        vec![],
+        vec!["disappear".to_string()],
+        vec!["setColorFX".to_string(), "setColorFX".to_string()],
+    ];
+    assert_eq!(expected, output_events);
+}
+
+/// Returns the name and the named targets of the events of each half
+/// of a two-high oak door, written with the naming rules.
+fn names_and_targets_of_door_halves(naming_rules: Vec<NamingRule>) -> Vec<(String, Vec<String>)> {
+    // An oak door, whose top half is the block above its bottom half.
+    let mut schematic_root = CompoundTag::new();
+    schematic_root.insert_i16("Length", 1);
+    schematic_root.insert_i16("Width", 1);
+    schematic_root.insert_i16("Height", 2);
+    schematic_root.insert_i8_vec("Blocks", vec![64, 64]);
+    schematic_root.insert_i8_vec("Data", vec![0, 8]);
+    let shaped_blocks = parse_shaped_blocks_from_model(&schematic_root, 4);
+    let bricks = extract_bricks_with_blocks_placed_from(
+        Vec::new(),
+        Vec::new(),
+        shaped_blocks.interactive_blocks,
+        Origin::Corner,
+        Elevation::PlaceOnGround,
+    );
+    let bls_writer = BlsWriter {
+        event_rules: interactive_block_rules(),
+        naming_rules,
+        ..BlsWriter::default()
+    };
+
+    let mut contents = Vec::new();
+    bls_writer.write_bricks(&bricks, &mut contents).unwrap();
+
+    let save_file = parse_save_file(&String::from_utf8(contents).unwrap()).unwrap();
+    save_file
+        .bricks
+        .iter()
+        .map(|saved_brick| {
+            let mut name = String::new();
+            let mut named_targets = Vec::new();
+            for attribute in &saved_brick.attributes {
+                match attribute {
+                    BrickAttribute::NtObjectName(object_name) => name = object_name.clone(),
+                    BrickAttribute::Event(brick_event) => {
+                        named_targets.push(brick_event.named_target.clone())
+                    }
+                    _ => {}
+                }
+            }
+            (name, named_targets)
+        })
+        .collect()
+}
+
+#[test]
+fn both_halves_of_two_high_door_open_it() {
+    let halves = names_and_targets_of_door_halves(Vec::new());
+
+    let expected = ("_door1".to_string(), vec!["_door1".to_string()]);
+    assert_eq!(halves, vec![expected.clone(), expected]);
+}
+
+#[test]
+fn naming_rules_leave_door_names_alone() {
+    let naming_rules = vec![NamingRule::parse("block=64:front").unwrap()];
+
+    let halves = names_and_targets_of_door_halves(naming_rules);
+
+    let expected = ("_door1".to_string(), vec!["_door1".to_string()]);
+    assert_eq!(halves, vec![expected.clone(), expected]);
+}
+
 #[test]
 fn place_4x_cube_tower() {
     let cube_sizes = [1; 4];
@@ -339,6 +520,86 @@ fn place_increasing_cubes_scale() {
     assert_eq!(expected, actual);
 }
 
+/// Returns the paths of every save file under the directory.
+fn save_files_in(directory: &Path) -> Vec<PathBuf> {
+    let mut save_file_paths = Vec::new();
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            save_file_paths.extend(save_files_in(&path));
+        } else if path.extension().is_some_and(|extension| extension == "bls") {
+            save_file_paths.push(path);
+        }
+    }
+
+    save_file_paths
+}
+
+#[test]
+fn read_every_comparison_save_file() {
+    let save_file_paths = save_files_in(Path::new("assets/brick_comparisons"));
+    assert!(!save_file_paths.is_empty());
+
+    for save_file_path in save_file_paths {
+        let contents = fs::read_to_string(&save_file_path).unwrap();
+
+        let save_file = parse_save_file(&contents)
+            .unwrap_or_else(|parse_error| panic!("{}: {}", save_file_path.display(), parse_error));
+
+        assert_eq!(save_file.linecount, save_file.bricks.len());
+        assert_eq!(save_file.to_string(), contents);
+    }
+}
+
+#[test]
+fn save_file_converts_back_into_same_model() {
+    let scaling_factor = 4;
+    let mut expected = VoxelGrid::new(7, 5, 6);
+    for x in 0..7 {
+        for y in 0..5 {
+            for z in 0..6 {
+                // A lumpy shape touching every side of its bounding
+                // box, so that nothing is lost to placement.
+                let is_filled = (x * 3 + y * 5 + z * 7) % 4 != 0 || z == 0;
+                expected.set(x, y, z, is_filled);
+            }
+        }
+    }
+
+    let largest_cubes = extract_largest_cubes_from(expected.clone(), scaling_factor);
+    let bricks = extract_bricks_placed_from(
+        largest_cubes,
+        Vec::new(),
+        Origin::Corner,
+        Elevation::default(),
+    );
+    let save_file_contents = to_save_file_output(&bricks);
+    let save_file = parse_save_file(&save_file_contents).unwrap();
+    let (voxel_grid, unknown_count) =
+        parse_grid_from_save_file(&save_file, &BrickCatalog::new(), scaling_factor);
+    let actual = parse_grid_from_model(grid_to_schematic(&voxel_grid));
+
+    assert_eq!(unknown_count, 0);
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn overlapping_8x_cubes_share_one_8x_cube() {
+    let save_file = parse_save_file(&paint_with_overlap(&[2, 2], Direction::XAxis)).unwrap();
+
+    let mut ui_names: Vec<&str> = save_file
+        .bricks
+        .iter()
+        .map(|saved_brick| saved_brick.ui_name.as_str())
+        .collect();
+    ui_names.sort_unstable();
+
+    assert_eq!(
+        ui_names,
+        vec!["4x Cube", "4x Cube", "4x Cube", "4x Cube", "8x Cube"]
+    );
+}
+
 // TODO: Create 2Overlapping8xCubes.bls from Blockland.
 //#[test]
 //fn place_two_overlapping_8x() {
@@ -0,0 +1,124 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+use nbt::encode::{write_compound_tag, write_gzip_compound_tag};
+use nbt::CompoundTag;
+use schematic2bls::blockland::mapping::{Elevation, Origin};
+use schematic2bls::blockland::save_file::{to_save_file_output, BlsWriter};
+use schematic2bls::largest_cube::CubeSizes;
+use schematic2bls::*;
+
+/// Returns a schematic of a few scattered towers, big enough to be
+/// read in more than one slab.
+fn towers_schematic(side: i16) -> CompoundTag {
+    let side_len = side as usize;
+    let blocks = (0..side_len * side_len * side_len)
+        .map(|idx| {
+            let (x, z) = (idx % side_len, idx / side_len % side_len);
+            if (x / 3 + z / 5) % 2 == 0 {
+                1
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    let mut schematic_root = CompoundTag::new();
+    schematic_root.insert_i16("Height", side);
+    schematic_root.insert_i16("Length", side);
+    schematic_root.insert_i16("Width", side);
+    schematic_root.insert_i8_vec("Blocks", blocks);
+
+    schematic_root
+}
+
+fn sorted_lines(save_file_content: &str) -> Vec<String> {
+    let mut lines = save_file_content
+        .lines()
+        .map(|line| line.to_string())
+        .collect::<Vec<String>>();
+    lines.sort();
+
+    lines
+}
+
+#[test]
+fn streamed_save_matches_whole_model() {
+    let scaling_factor = 4;
+    let cube_sizes = CubeSizes::new(&[4, 8]);
+    let schematic_root = towers_schematic(20);
+
+    let temp_dir = env::temp_dir().join(format!("schematic2bls-streaming-{}", std::process::id()));
+    fs::create_dir_all(&temp_dir).unwrap();
+    let schematic_path = temp_dir.join("towers.schematic");
+    let save_file_path = temp_dir.join("towers.bls");
+    write_gzip_compound_tag(&mut File::create(&schematic_path).unwrap(), &schematic_root).unwrap();
+
+    let voxel_grid = parse_grid_from_model(load_schematic(&schematic_path));
+    let largest_cubes = extract_largest_cubes_with(voxel_grid, scaling_factor, &cube_sizes);
+    let bricks = extract_bricks_placed_from(
+        largest_cubes,
+        Vec::new(),
+        Origin::Corner,
+        Elevation::PlaceOnGround,
+    );
+    let expected = to_save_file_output(&bricks);
+
+    let brick_count = stream_save_file_from(
+        &schematic_path,
+        save_file_path.to_str().unwrap().to_string(),
+        scaling_factor,
+        &cube_sizes,
+        3,
+        Origin::Corner,
+        Elevation::PlaceOnGround,
+        &BlsWriter::default(),
+    )
+    .unwrap();
+    let actual = fs::read_to_string(&save_file_path).unwrap();
+    fs::remove_dir_all(&temp_dir).unwrap();
+
+    assert!(brick_count > 0);
+    assert_eq!(sorted_lines(&expected), sorted_lines(&actual));
+}
+
+#[test]
+fn scratch_file_removed_when_schematic_is_cut_short() {
+    let temp_dir = env::temp_dir().join(format!(
+        "schematic2bls-streaming-cut-{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&temp_dir).unwrap();
+    let schematic_path = temp_dir.join("towers.schematic");
+    let save_file_path = temp_dir.join("towers.bls");
+
+    // The blocks run out partway through, after the first slabs have
+    // been written to the scratch file.
+    let mut schematic_bytes = Vec::new();
+    write_compound_tag(&mut schematic_bytes, &towers_schematic(20)).unwrap();
+    schematic_bytes.truncate(schematic_bytes.len() / 2);
+    let mut encoder = GzEncoder::new(
+        File::create(&schematic_path).unwrap(),
+        Compression::default(),
+    );
+    encoder.write_all(&schematic_bytes).unwrap();
+    encoder.finish().unwrap();
+
+    let streamed = stream_save_file_from(
+        &schematic_path,
+        save_file_path.to_str().unwrap().to_string(),
+        4,
+        &CubeSizes::default(),
+        1,
+        Origin::Corner,
+        Elevation::PlaceOnGround,
+        &BlsWriter::default(),
+    );
+    let scratch_left_behind = temp_dir.join("towers.bls.part").exists();
+    fs::remove_dir_all(&temp_dir).unwrap();
+
+    assert!(streamed.is_err());
+    assert!(!scratch_left_behind);
+}